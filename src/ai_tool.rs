@@ -1,32 +1,197 @@
-use std::process::Command;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
-pub enum AiTool {
+use crate::{
+    config::{Config, UserConfig},
+    session_log::SessionLog,
+};
+
+/// 既定で使うツール
+pub const DEFAULT_TOOL: &str = "claude";
+
+/// プロンプトをツールに渡す方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptMode {
+    /// 最後の位置引数として渡す
     #[default]
-    Claude,
-    Codex,
+    Arg,
+    /// `prompt_flag` の値として渡す (`--prompt <PROMPT>`)
+    Flag,
+    /// 標準入力に書き込む
+    Stdin,
+    /// ファイルに書き出してパスを渡す (`prompt_flag` があればその値として渡す)
+    File,
+}
+
+/// 起動したツールに渡す内容
+#[derive(Debug, Clone, Copy)]
+pub enum SessionInput<'a> {
+    /// プロンプトを渡して新しいセッションを始める (`file` は `PromptMode::File` で書き出す先)
+    Prompt {
+        text: &'a str,
+        file: Option<&'a Path>,
+    },
+    /// `resume_args` で前回のセッションを再開する
+    Resume,
+}
+
+/// `[tools.<name>]` に書く AI ツールの定義
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolSpec {
+    /// 表示名 (省略時はツール名)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// 実行ファイル名 (省略時はツール名)
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub executable: String,
+    /// インストール確認に使う引数 (空なら確認しない)
+    pub version_args: Vec<String>,
+    /// 常に渡す引数
+    pub args: Vec<String>,
+    pub prompt: PromptMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_flag: Option<String>,
+    /// `--plan` のときに追加する引数
+    pub plan_args: Vec<String>,
+    /// `--headless` のときに先頭に追加する引数 (未設定のツールはヘッドレス実行できない)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headless_args: Option<Vec<String>>,
+    /// 前回のセッションを再開するときに先頭に追加する引数 (未設定のツールは再開用のプロンプトを渡す)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resume_args: Option<Vec<String>>,
+    /// 追加する環境変数
+    pub env: BTreeMap<String, String>,
+    /// Windows で npm の `.cmd` を経由せず node で直接起動するスクリプト
+    /// (`.cmd` のあるディレクトリからの相対パス)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub windows_script: Option<String>,
+}
+
+impl Default for ToolSpec {
+    fn default() -> Self {
+        Self {
+            display_name: None,
+            executable: String::new(),
+            version_args: vec!["--version".to_string()],
+            args: Vec::new(),
+            prompt: PromptMode::default(),
+            prompt_flag: None,
+            plan_args: Vec::new(),
+            headless_args: None,
+            resume_args: None,
+            env: BTreeMap::new(),
+            windows_script: None,
+        }
+    }
+}
+
+/// 組み込みのツール定義
+pub fn builtin_tools() -> BTreeMap<String, ToolSpec> {
+    let claude = ToolSpec {
+        display_name: Some("Claude".to_string()),
+        executable: "claude".to_string(),
+        plan_args: vec!["--permission-mode".to_string(), "plan".to_string()],
+        headless_args: Some(vec!["--print".to_string()]),
+        resume_args: Some(vec!["--continue".to_string()]),
+        windows_script: Some("node_modules\\@anthropic-ai\\claude-code\\cli.js".to_string()),
+        ..Default::default()
+    };
+    let codex = ToolSpec {
+        display_name: Some("Codex".to_string()),
+        executable: "codex".to_string(),
+        headless_args: Some(vec!["exec".to_string()]),
+        resume_args: Some(vec!["resume".to_string(), "--last".to_string()]),
+        windows_script: Some("node_modules\\codex\\cli.js".to_string()),
+        ..Default::default()
+    };
+    BTreeMap::from([("claude".to_string(), claude), ("codex".to_string(), codex)])
+}
+
+/// 組み込み・ユーザー設定・リポジトリ設定を重ねたツールの一覧
+/// 同名のツールは後から読んだ定義で丸ごと置き換える
+pub struct ToolRegistry {
+    tools: BTreeMap<String, ToolSpec>,
+    default_tool: String,
+}
+
+impl ToolRegistry {
+    pub fn load(config: &Config) -> Result<ToolRegistry> {
+        Ok(Self::from_layers(&UserConfig::load()?, config))
+    }
+
+    pub fn from_layers(user: &UserConfig, config: &Config) -> ToolRegistry {
+        let mut tools = builtin_tools();
+        tools.extend(user.tools.clone());
+        tools.extend(config.tools.clone());
+        let default_tool = config
+            .default_tool
+            .clone()
+            .or_else(|| user.default_tool.clone())
+            .unwrap_or_else(|| DEFAULT_TOOL.to_string());
+        ToolRegistry {
+            tools,
+            default_tool,
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.tools.keys().map(String::as_str)
+    }
+
+    /// 名前でツールを選ぶ (None なら既定のツール)
+    pub fn resolve(&self, name: Option<&str>) -> Result<AiTool> {
+        let name = name.unwrap_or(&self.default_tool);
+        let Some(spec) = self.tools.get(name) else {
+            bail!(
+                "ツール \"{}\" は定義されていません。利用できるツール: {}",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            );
+        };
+        Ok(AiTool {
+            name: name.to_string(),
+            spec: spec.clone(),
+        })
+    }
+}
+
+/// 起動するツール
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AiTool {
+    pub name: String,
+    pub spec: ToolSpec,
 }
 
 impl AiTool {
     /// Returns the command name to execute
     pub fn command_name(&self) -> &str {
-        match self {
-            AiTool::Claude => "claude",
-            AiTool::Codex => "codex",
+        if self.spec.executable.is_empty() {
+            &self.name
+        } else {
+            &self.spec.executable
         }
     }
 
     /// Returns the command name with platform-specific adjustments
-    /// On Windows, this adds .cmd extension for npm-installed commands
+    /// On Windows, this resolves PATHEXT and falls back to the npm `.cmd` wrapper
     pub fn executable_command(&self) -> String {
         let base_name = self.command_name();
 
         #[cfg(windows)]
         {
-            // On Windows, try to use .cmd extension for npm-installed commands
-            format!("{}.cmd", base_name)
+            match which::which(base_name) {
+                Ok(path) => path.display().to_string(),
+                Err(_) => format!("{}.cmd", base_name),
+            }
         }
 
         #[cfg(not(windows))]
@@ -37,19 +202,16 @@ impl AiTool {
 
     /// Returns a human-readable display name
     pub fn display_name(&self) -> &str {
-        match self {
-            AiTool::Claude => "Claude",
-            AiTool::Codex => "Codex",
-        }
+        self.spec.display_name.as_deref().unwrap_or(&self.name)
     }
 
     /// Checks if the tool is installed and available
     pub fn check_installed(&self) -> Result<()> {
-        let command_name = self.command_name();
-        let executable = self.executable_command();
-
-        let output = Command::new(&executable)
-            .arg("--version")
+        if self.spec.version_args.is_empty() {
+            return Ok(());
+        }
+        let output = Command::new(self.executable_command())
+            .args(&self.spec.version_args)
             .output();
 
         match output {
@@ -57,30 +219,375 @@ impl AiTool {
             _ => bail!(
                 "{} CLI ({}) がインストールされていません。",
                 self.display_name(),
-                command_name
+                self.command_name()
             ),
         }
     }
+
+    /// 起動時の引数 (`prompt_file` は `PromptMode::File` のときに渡すパス)
+    pub fn launch_args(&self, prompt: &str, prompt_file: Option<&Path>, plan: bool) -> Vec<String> {
+        let mut args = self.spec.args.clone();
+        if plan {
+            args.extend(self.spec.plan_args.iter().cloned());
+        }
+        let flag = self.spec.prompt_flag.iter().cloned();
+        match self.spec.prompt {
+            PromptMode::Arg => args.push(prompt.to_string()),
+            PromptMode::Flag => {
+                args.extend(flag);
+                args.push(prompt.to_string());
+            }
+            PromptMode::File => {
+                args.extend(flag);
+                if let Some(path) = prompt_file {
+                    args.push(path.display().to_string());
+                }
+            }
+            PromptMode::Stdin => {}
+        }
+        args
+    }
+
+    /// ヘッドレス実行時の引数 (`headless_args` の後に通常の起動引数を続ける)
+    pub fn headless_launch_args(
+        &self,
+        prompt: &str,
+        prompt_file: Option<&Path>,
+        plan: bool,
+    ) -> Result<Vec<String>> {
+        let Some(headless_args) = &self.spec.headless_args else {
+            bail!(
+                "ツール \"{}\" はヘッドレス実行に対応していません。\n\
+                 [tools.{}] に headless_args を設定してください。",
+                self.name,
+                self.name
+            );
+        };
+        let mut args = headless_args.clone();
+        args.extend(self.launch_args(prompt, prompt_file, plan));
+        Ok(args)
+    }
+
+    /// 再開時の引数 (`resume_args` の後に通常の引数を続ける。未設定なら None)
+    pub fn resume_launch_args(&self, plan: bool) -> Option<Vec<String>> {
+        let mut args = self.spec.resume_args.clone()?;
+        args.extend(self.spec.args.iter().cloned());
+        if plan {
+            args.extend(self.spec.plan_args.iter().cloned());
+        }
+        Some(args)
+    }
+
+    /// 起動時の引数と標準入力に書き込む内容
+    fn session_args<'a>(
+        &self,
+        input: SessionInput<'a>,
+        plan: bool,
+    ) -> Result<(Vec<String>, Option<&'a str>)> {
+        match input {
+            SessionInput::Prompt { text, file } => {
+                let file = self.prepare_prompt(text, file)?;
+                let stdin = (self.spec.prompt == PromptMode::Stdin).then_some(text);
+                Ok((self.launch_args(text, file, plan), stdin))
+            }
+            SessionInput::Resume => match self.resume_launch_args(plan) {
+                Some(args) => Ok((args, None)),
+                None => bail!(
+                    "ツール \"{}\" には resume_args が設定されていません",
+                    self.name
+                ),
+            },
+        }
+    }
+
+    /// ワークスペースでツールを起動する
+    /// Unix では exec で置き換わるため、標準入力で渡す場合を除いて戻らない
+    pub fn launch(&self, workspace_path: &Path, input: SessionInput, plan: bool) -> Result<()> {
+        let (args, stdin_input) = self.session_args(input, plan)?;
+        let mut command = self.command(workspace_path, &args);
+
+        if let Some(text) = stdin_input {
+            command.stdin(Stdio::piped());
+            let mut child = command
+                .spawn()
+                .with_context(|| format!("{} の起動に失敗しました", self.display_name()))?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(text.as_bytes())
+                    .context("プロンプトの書き込みに失敗しました")?;
+            }
+            return wait_success(self, child.wait()?);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            let err = command.exec();
+            // exec only returns on error
+            Err(anyhow!(
+                "{} の起動に失敗しました: {}",
+                self.display_name(),
+                err
+            ))
+        }
+
+        #[cfg(not(unix))]
+        {
+            let status = command
+                .status()
+                .with_context(|| format!("{} の起動に失敗しました", self.display_name()))?;
+            wait_success(self, status)
+        }
+    }
+
+    /// 擬似端末の上でツールを起動し、端末に表示しながら出力を `log` に記録する
+    #[cfg(unix)]
+    pub fn launch_recorded(
+        &self,
+        workspace_path: &Path,
+        input: SessionInput,
+        plan: bool,
+        log: &mut SessionLog,
+    ) -> Result<()> {
+        let (args, stdin_input) = self.session_args(input, plan)?;
+        let command = self.command(workspace_path, &args);
+        let status = crate::recording::run_in_pty(command, stdin_input, log)
+            .with_context(|| format!("{} の起動に失敗しました", self.display_name()))?;
+        log.finish(Some(status))?;
+        wait_success(self, status)
+    }
+
+    /// 対話せずに子プロセスとして起動し、標準出力と標準エラーを `log` に書き込む
+    pub fn spawn_headless(
+        &self,
+        workspace_path: &Path,
+        prompt: &str,
+        prompt_file: Option<&Path>,
+        plan: bool,
+        log: &SessionLog,
+    ) -> Result<Child> {
+        let prompt_file = self.prepare_prompt(prompt, prompt_file)?;
+        let args = self.headless_launch_args(prompt, prompt_file, plan)?;
+        let mut command = self.command(workspace_path, &args);
+        command
+            .stdout(log.output_file()?)
+            .stderr(log.output_file()?);
+        if self.spec.prompt == PromptMode::Stdin {
+            command.stdin(Stdio::piped());
+        } else {
+            command.stdin(Stdio::null());
+        }
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("{} の起動に失敗しました", self.display_name()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(prompt.as_bytes())
+                .context("プロンプトの書き込みに失敗しました")?;
+        }
+        Ok(child)
+    }
+
+    /// 設定を検証し、`PromptMode::File` ならプロンプトをファイルに書き出してそのパスを返す
+    fn prepare_prompt<'a>(
+        &self,
+        prompt: &str,
+        prompt_file: Option<&'a Path>,
+    ) -> Result<Option<&'a Path>> {
+        if self.spec.prompt == PromptMode::Flag && self.spec.prompt_flag.is_none() {
+            bail!(
+                "ツール \"{}\" は prompt = \"flag\" ですが prompt_flag が設定されていません。",
+                self.name
+            );
+        }
+        match (self.spec.prompt, prompt_file) {
+            (PromptMode::File, Some(path)) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).with_context(|| {
+                        format!("ディレクトリの作成に失敗しました: {}", parent.display())
+                    })?;
+                }
+                std::fs::write(path, prompt).with_context(|| {
+                    format!(
+                        "プロンプトファイルの書き込みに失敗しました: {}",
+                        path.display()
+                    )
+                })?;
+                Ok(Some(path))
+            }
+            (PromptMode::File, None) => bail!("プロンプトファイルのパスが指定されていません"),
+            _ => Ok(None),
+        }
+    }
+
+    /// 引数・環境変数・作業ディレクトリを設定したコマンド
+    fn command(&self, workspace_path: &Path, args: &[String]) -> Command {
+        let mut command = self.base_command();
+        command
+            .args(args)
+            .envs(&self.spec.env)
+            .current_dir(workspace_path);
+        command
+    }
+
+    /// 実行するコマンド (Windows では可能なら node でスクリプトを直接起動する)
+    fn base_command(&self) -> Command {
+        #[cfg(windows)]
+        {
+            // npm の .cmd ラッパーは長い引数や複数行の引数を正しく扱えないため、
+            // cli.js を node で直接起動する
+            if let Some(script) = self.windows_script_path() {
+                let mut command = Command::new("node");
+                command.arg(script);
+                return command;
+            }
+        }
+        Command::new(self.executable_command())
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn windows_script_path(&self) -> Option<PathBuf> {
+        let script = self.spec.windows_script.as_ref()?;
+        let cmd_path = which::which(format!("{}.cmd", self.command_name())).ok()?;
+        let path = cmd_path.parent()?.join(script);
+        path.exists().then_some(path)
+    }
+}
+
+fn wait_success(tool: &AiTool, status: std::process::ExitStatus) -> Result<()> {
+    if !status.success() {
+        bail!("{} が異常終了しました", tool.display_name());
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn builtin(name: &str) -> AiTool {
+        ToolRegistry::from_layers(&UserConfig::default(), &Config::default())
+            .resolve(Some(name))
+            .unwrap()
+    }
+
     #[test]
     fn test_command_names() {
-        assert_eq!(AiTool::Claude.command_name(), "claude");
-        assert_eq!(AiTool::Codex.command_name(), "codex");
+        assert_eq!(builtin("claude").command_name(), "claude");
+        assert_eq!(builtin("codex").command_name(), "codex");
     }
 
     #[test]
     fn test_display_names() {
-        assert_eq!(AiTool::Claude.display_name(), "Claude");
-        assert_eq!(AiTool::Codex.display_name(), "Codex");
+        assert_eq!(builtin("claude").display_name(), "Claude");
+        assert_eq!(builtin("codex").display_name(), "Codex");
+    }
+
+    #[test]
+    fn test_default_tool() {
+        let registry = ToolRegistry::from_layers(&UserConfig::default(), &Config::default());
+        assert_eq!(registry.resolve(None).unwrap().name, "claude");
+        assert!(registry.resolve(Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_repo_config_overrides_user_config() {
+        let user: UserConfig = toml::from_str(
+            r#"
+            default_tool = "gemini"
+
+            [tools.gemini]
+            executable = "gemini"
+            prompt = "flag"
+            prompt_flag = "--prompt-interactive"
+
+            [tools.aider]
+            executable = "aider"
+            "#,
+        )
+        .unwrap();
+        let config: Config = toml::from_str(
+            r#"
+            repo_url = "git@example.com:o/r.git"
+
+            [tools.aider]
+            executable = "aider-wrapper"
+            prompt = "file"
+            prompt_flag = "--message-file"
+            env = { AIDER_AUTO_COMMITS = "false" }
+            "#,
+        )
+        .unwrap();
+        let registry = ToolRegistry::from_layers(&user, &config);
+
+        let gemini = registry.resolve(None).unwrap();
+        assert_eq!(gemini.name, "gemini");
+        assert_eq!(gemini.spec.version_args, vec!["--version".to_string()]);
+        let aider = registry.resolve(Some("aider")).unwrap();
+        assert_eq!(aider.command_name(), "aider-wrapper");
+        assert_eq!(aider.spec.env["AIDER_AUTO_COMMITS"], "false");
+        assert!(registry.resolve(Some("codex")).is_ok());
+    }
+
+    #[test]
+    fn test_launch_args_by_prompt_mode() {
+        let mut tool = builtin("claude");
+        assert_eq!(
+            tool.launch_args("fix it", None, true),
+            vec!["--permission-mode", "plan", "fix it"]
+        );
+
+        tool.spec.prompt = PromptMode::Flag;
+        tool.spec.prompt_flag = Some("--prompt".to_string());
+        assert_eq!(
+            tool.launch_args("fix it", None, false),
+            vec!["--prompt", "fix it"]
+        );
+
+        tool.spec.prompt = PromptMode::File;
+        tool.spec.prompt_flag = None;
+        let path = PathBuf::from("/tmp/prompt.md");
+        assert_eq!(
+            tool.launch_args("fix it", Some(&path), false),
+            vec!["/tmp/prompt.md"]
+        );
+
+        tool.spec.prompt = PromptMode::Stdin;
+        tool.spec.args = vec!["--yes".to_string()];
+        assert_eq!(tool.launch_args("fix it", None, false), vec!["--yes"]);
+    }
+
+    #[test]
+    fn test_headless_launch_args() {
+        let tool = builtin("claude");
+        assert_eq!(
+            tool.headless_launch_args("fix it", None, false).unwrap(),
+            vec!["--print", "fix it"]
+        );
+
+        let mut custom = builtin("codex");
+        custom.spec.headless_args = None;
+        assert!(custom.headless_launch_args("fix it", None, false).is_err());
     }
 
     #[test]
-    fn test_default_trait() {
-        assert_eq!(AiTool::default(), AiTool::Claude);
+    fn test_resume_launch_args() {
+        assert_eq!(
+            builtin("claude").resume_launch_args(true),
+            Some(vec![
+                "--continue".to_string(),
+                "--permission-mode".to_string(),
+                "plan".to_string()
+            ])
+        );
+        assert_eq!(
+            builtin("codex").resume_launch_args(false),
+            Some(vec!["resume".to_string(), "--last".to_string()])
+        );
+
+        let mut custom = builtin("codex");
+        custom.spec.resume_args = None;
+        assert_eq!(custom.resume_launch_args(false), None);
+        assert!(custom.session_args(SessionInput::Resume, false).is_err());
     }
 }
@@ -0,0 +1,303 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    process::Child,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    ai_tool::AiTool,
+    output,
+    repo::RepoManager,
+    session_log::SessionLog,
+    start::{fetch_issue, issue_branch_name, prepare_task, PreparedTask, StartOptions},
+    state::format_age,
+};
+
+/// 実行中のセッションを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// 端末に表示している表を書き直す間隔
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `start --headless` の実行オプション
+pub struct BatchOptions {
+    /// 同時に実行するセッションの上限
+    pub max_parallel: usize,
+}
+
+enum SessionState {
+    Pending,
+    Running {
+        child: Child,
+        started: Instant,
+    },
+    Finished {
+        elapsed: Duration,
+    },
+    Failed {
+        reason: String,
+        elapsed: Option<Duration>,
+    },
+}
+
+struct Session {
+    issue_number: u32,
+    task: Option<PreparedTask>,
+    log: Option<SessionLog>,
+    state: SessionState,
+}
+
+impl Session {
+    fn task_name(&self) -> &str {
+        self.task
+            .as_ref()
+            .map(|task| task.task_name.as_str())
+            .unwrap_or("-")
+    }
+
+    fn status(&self) -> String {
+        match &self.state {
+            SessionState::Pending => "待機中".to_string(),
+            SessionState::Running { .. } => "実行中".to_string(),
+            SessionState::Finished { .. } => "完了".to_string(),
+            SessionState::Failed { reason, .. } => format!("失敗 ({})", reason),
+        }
+    }
+
+    fn elapsed(&self) -> String {
+        match &self.state {
+            SessionState::Running { started, .. } => format_age(started.elapsed()),
+            SessionState::Finished { elapsed } => format_age(*elapsed),
+            SessionState::Failed {
+                elapsed: Some(elapsed),
+                ..
+            } => format_age(*elapsed),
+            _ => "-".to_string(),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        matches!(
+            self.state,
+            SessionState::Finished { .. } | SessionState::Failed { .. }
+        )
+    }
+
+    fn row(&self) -> Vec<String> {
+        vec![
+            self.task_name().to_string(),
+            format!("#{}", self.issue_number),
+            self.status(),
+            self.elapsed(),
+            self.log
+                .as_ref()
+                .map(|log| &log.path)
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "-".to_string()),
+        ]
+    }
+}
+
+/// 複数の Issue のワークスペースを作成し、AI ツールをヘッドレスで並列実行する
+///
+/// 各セッションの標準出力と標準エラーは `.aicloner/logs/<task>/` に書き込み、
+/// 実行中は状況を表で表示する。1 件でも失敗したらエラーを返す。
+pub fn run_headless(
+    manager: &RepoManager,
+    issue_numbers: &[u32],
+    ai_tool: &AiTool,
+    options: &StartOptions,
+    batch: &BatchOptions,
+) -> Result<()> {
+    // ツールがヘッドレス実行に対応しているかをワークスペース作成前に確認する
+    ai_tool.headless_launch_args("", None, options.plan)?;
+
+    let mut sessions: Vec<Session> = issue_numbers
+        .iter()
+        .map(|&issue_number| {
+            let mut session = Session {
+                issue_number,
+                task: None,
+                log: None,
+                state: SessionState::Pending,
+            };
+            let prepared = fetch_issue(issue_number, manager).and_then(|issue| {
+                let branch_name =
+                    issue_branch_name(manager, issue_number, issue.as_ref(), ai_tool)?;
+                prepare_task(
+                    issue_number,
+                    issue.as_ref(),
+                    &branch_name,
+                    ai_tool,
+                    manager,
+                    options,
+                )
+            });
+            match prepared {
+                Ok(task) => session.task = Some(task),
+                Err(err) => {
+                    eprintln!("✗ Issue #{}: {:#}", issue_number, err);
+                    session.state = SessionState::Failed {
+                        reason: "準備".to_string(),
+                        elapsed: None,
+                    };
+                }
+            }
+            session
+        })
+        .collect();
+
+    let max_parallel = batch.max_parallel.max(1);
+    let live = io::stdout().is_terminal();
+    let mut drawn_lines = 0;
+    let mut last_draw: Option<Instant> = None;
+    let mut reported: Vec<String> = sessions.iter().map(|_| String::new()).collect();
+    println!();
+    loop {
+        let mut changed = false;
+
+        let running = sessions
+            .iter()
+            .filter(|session| matches!(session.state, SessionState::Running { .. }))
+            .count();
+        let slots = max_parallel.saturating_sub(running);
+        for session in sessions
+            .iter_mut()
+            .filter(|session| matches!(session.state, SessionState::Pending))
+            .take(slots)
+        {
+            start_session(manager, ai_tool, options, session);
+            changed = true;
+        }
+
+        for session in &mut sessions {
+            if poll_session(session) {
+                changed = true;
+            }
+        }
+
+        let finished = sessions.iter().all(Session::is_done);
+        if live {
+            let due = last_draw.is_none_or(|at| at.elapsed() >= REDRAW_INTERVAL);
+            if changed || due || finished {
+                drawn_lines = redraw(&sessions, drawn_lines)?;
+                last_draw = Some(Instant::now());
+            }
+        } else if changed {
+            for (session, reported) in sessions.iter().zip(&mut reported) {
+                let status = session.status();
+                if *reported != status {
+                    println!(
+                        "{} (#{}): {}",
+                        session.task_name(),
+                        session.issue_number,
+                        status
+                    );
+                    *reported = status;
+                }
+            }
+        }
+
+        if finished {
+            break;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    if !live {
+        println!();
+        print_summary(&sessions);
+    }
+
+    let failed = sessions
+        .iter()
+        .filter(|session| matches!(session.state, SessionState::Failed { .. }))
+        .count();
+    if failed > 0 {
+        bail!("{} 件のセッションが失敗しました", failed);
+    }
+    Ok(())
+}
+
+/// ログファイルを作成してセッションを起動する
+fn start_session(
+    manager: &RepoManager,
+    ai_tool: &AiTool,
+    options: &StartOptions,
+    session: &mut Session,
+) {
+    let Some(task) = &session.task else {
+        return;
+    };
+    let started = Instant::now();
+    let result = SessionLog::create(manager, &task.task_name, &ai_tool.name).and_then(|log| {
+        let log = session.log.insert(log);
+        ai_tool.spawn_headless(
+            &task.workspace_path,
+            &task.prompt,
+            Some(&task.prompt_file),
+            options.plan,
+            log,
+        )
+    });
+    session.state = match result {
+        Ok(child) => SessionState::Running { child, started },
+        Err(err) => {
+            eprintln!("✗ {}: {:#}", task.task_name, err);
+            SessionState::Failed {
+                reason: "起動".to_string(),
+                elapsed: None,
+            }
+        }
+    };
+}
+
+/// 実行中のセッションが終了していれば状態を更新し、更新したら true を返す
+fn poll_session(session: &mut Session) -> bool {
+    let SessionState::Running { child, started } = &mut session.state else {
+        return false;
+    };
+    let elapsed = started.elapsed();
+    let result = child.try_wait();
+    if let (Ok(Some(status)), Some(log)) = (&result, &mut session.log) {
+        if let Err(err) = log.finish(Some(*status)) {
+            eprintln!("警告: {:#}", err);
+        }
+    }
+    session.state = match result {
+        Ok(None) => return false,
+        Ok(Some(status)) if status.success() => SessionState::Finished { elapsed },
+        Ok(Some(status)) => SessionState::Failed {
+            reason: match status.code() {
+                Some(code) => format!("終了コード {}", code),
+                None => "シグナルで終了".to_string(),
+            },
+            elapsed: Some(elapsed),
+        },
+        Err(err) => SessionState::Failed {
+            reason: err.to_string(),
+            elapsed: Some(elapsed),
+        },
+    };
+    true
+}
+
+const HEADERS: [&str; 5] = ["TASK", "ISSUE", "STATUS", "ELAPSED", "LOG"];
+
+fn print_summary(sessions: &[Session]) {
+    let rows: Vec<Vec<String>> = sessions.iter().map(Session::row).collect();
+    output::print_table(&HEADERS, &rows);
+}
+
+/// 前回表示した表を消して書き直し、表示した行数を返す
+fn redraw(sessions: &[Session], drawn_lines: usize) -> Result<usize> {
+    let mut stdout = io::stdout();
+    if drawn_lines > 0 {
+        write!(stdout, "\x1b[{}A\x1b[J", drawn_lines)?;
+    }
+    print_summary(sessions);
+    stdout.flush()?;
+    Ok(sessions.len() + 1)
+}
@@ -0,0 +1,278 @@
+use std::env;
+
+use anyhow::{bail, Context, Result};
+
+use crate::{repo::run_command_quiet, state};
+
+/// `branch_template` の既定値
+pub const DEFAULT_BRANCH_TEMPLATE: &str = "aicloner-issue{issue}";
+/// `{slug}` に使う Issue タイトルの最大文字数
+const SLUG_MAX_LEN: usize = 40;
+
+/// `branch_template` で使えるプレースホルダ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Placeholder {
+    Issue,
+    Slug,
+    User,
+    Tool,
+    Date,
+}
+
+impl Placeholder {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "issue" => Some(Self::Issue),
+            "slug" => Some(Self::Slug),
+            "user" => Some(Self::User),
+            "tool" => Some(Self::Tool),
+            "date" => Some(Self::Date),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+/// ブランチ名に埋め込む値
+pub struct BranchVars<'a> {
+    pub issue_number: u32,
+    /// `{slug}` の元にする Issue のタイトル (取得できなければ空)
+    pub issue_title: &'a str,
+    pub tool: &'a str,
+}
+
+impl BranchVars<'_> {
+    fn value(&self, placeholder: Placeholder) -> String {
+        match placeholder {
+            Placeholder::Issue => self.issue_number.to_string(),
+            Placeholder::Slug => match slugify(self.issue_title, SLUG_MAX_LEN) {
+                slug if slug.is_empty() => "issue".to_string(),
+                slug => slug,
+            },
+            Placeholder::User => current_user(),
+            Placeholder::Tool => slugify(self.tool, SLUG_MAX_LEN),
+            Placeholder::Date => state::now_timestamp()
+                .chars()
+                .take(10)
+                .filter(char::is_ascii_digit)
+                .collect(),
+        }
+    }
+}
+
+/// テンプレートにプレースホルダの値を埋め込んでブランチ名にする
+pub fn render(template: &str, vars: &BranchVars) -> Result<String> {
+    let mut branch = String::new();
+    for segment in parse(template)? {
+        match segment {
+            Segment::Literal(text) => branch.push_str(&text),
+            Segment::Placeholder(placeholder) => branch.push_str(&vars.value(placeholder)),
+        }
+    }
+    Ok(branch)
+}
+
+/// テンプレートの書式と、展開した名前がブランチ名として使えるかを確認する
+pub fn validate_template(template: &str) -> Result<()> {
+    let segments = parse(template)?;
+    if !segments.contains(&Segment::Placeholder(Placeholder::Issue)) {
+        bail!(
+            "branch_template には {{issue}} を含めてください: {}",
+            template
+        );
+    }
+    let sample = BranchVars {
+        issue_number: 1,
+        issue_title: "sample issue",
+        tool: "claude",
+    };
+    check_ref_format(&render(template, &sample)?)
+        .with_context(|| format!("branch_template が不正です: {}", template))
+}
+
+/// `git check-ref-format --branch` でブランチ名として使えるかを確認する
+pub fn check_ref_format(branch: &str) -> Result<()> {
+    let args = vec![
+        "check-ref-format".to_string(),
+        "--branch".to_string(),
+        branch.to_string(),
+    ];
+    if branch.starts_with('-') || run_command_quiet("git", &args, None).is_err() {
+        bail!("\"{}\" はブランチ名として使えません", branch);
+    }
+    Ok(())
+}
+
+/// ブランチに対応するタスク名 (ワークスペースのディレクトリ名)
+/// `/` を含むブランチでもディレクトリが入れ子にならないよう `-` に置き換える
+pub fn task_name(branch: &str) -> String {
+    branch.replace('/', "-")
+}
+
+/// `name` がテンプレートから作った Issue のブランチ (`-2` などの接尾辞付きを含む) かどうか
+pub fn matches_issue(template: &str, name: &str, issue_number: u32) -> bool {
+    let Ok(segments) = parse(template) else {
+        return false;
+    };
+    let issue = issue_number.to_string();
+    match_segments(&segments, name, &issue)
+        || name
+            .match_indices('-')
+            .any(|(end, _)| match_segments(&segments, &name[..end], &issue))
+}
+
+/// タイトルを ASCII に変換し、英数字を `-` でつないだ `max_len` 文字以内の文字列にする
+pub fn slugify(text: &str, max_len: usize) -> String {
+    let mut slug = String::new();
+    for ch in deunicode::deunicode(text).chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(max_len);
+    slug.trim_end_matches('-').to_string()
+}
+
+fn parse(template: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        if start > 0 {
+            segments.push(Segment::Literal(rest[..start].to_string()));
+        }
+        let Some(end) = rest[start..].find('}') else {
+            bail!("branch_template の {{ が閉じられていません: {}", template);
+        };
+        let name = &rest[start + 1..start + end];
+        let Some(placeholder) = Placeholder::parse(name) else {
+            bail!(
+                "branch_template の {{{}}} は使えません \
+                 (使えるのは {{issue}} {{slug}} {{user}} {{tool}} {{date}}): {}",
+                name,
+                template
+            );
+        };
+        segments.push(Segment::Placeholder(placeholder));
+        rest = &rest[start + end + 1..];
+    }
+    if rest.contains('}') {
+        bail!("branch_template に対応しない }} があります: {}", template);
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Literal(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+/// `{issue}` は番号と一致する部分、それ以外のプレースホルダは 1 文字以上の任意の部分に対応させる
+fn match_segments(segments: &[Segment], text: &str, issue: &str) -> bool {
+    match segments.split_first() {
+        None => text.is_empty(),
+        Some((Segment::Literal(literal), rest)) => text
+            .strip_prefix(literal.as_str())
+            .is_some_and(|text| match_segments(rest, text, issue)),
+        Some((Segment::Placeholder(Placeholder::Issue), rest)) => text
+            .strip_prefix(issue)
+            .is_some_and(|text| match_segments(rest, text, issue)),
+        Some((Segment::Placeholder(_), rest)) => (1..=text.len())
+            .filter(|&end| text.is_char_boundary(end))
+            .any(|end| match_segments(rest, &text[end..], issue)),
+    }
+}
+
+/// `{user}` の値 (git の user.name、無ければログインユーザー名)
+fn current_user() -> String {
+    let args = vec!["config".to_string(), "user.name".to_string()];
+    let name = run_command_quiet("git", &args, None)
+        .ok()
+        .or_else(|| env::var("USER").ok())
+        .or_else(|| env::var("USERNAME").ok())
+        .unwrap_or_default();
+    match slugify(&name, SLUG_MAX_LEN) {
+        user if user.is_empty() => "user".to_string(),
+        user => user,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            slugify("Fix login: crash on \"Save\"", 40),
+            "fix-login-crash-on-save"
+        );
+        assert_eq!(slugify("Café déjà vu", 40), "cafe-deja-vu");
+        assert_eq!(slugify("ログイン", 40), "roguin");
+        assert_eq!(
+            slugify("a very long issue title that keeps going", 16),
+            "a-very-long-issu"
+        );
+        assert_eq!(slugify("trailing words here", 9), "trailing");
+        assert_eq!(slugify("!!!", 40), "");
+    }
+
+    #[test]
+    fn test_render_and_validate() {
+        let vars = BranchVars {
+            issue_number: 12,
+            issue_title: "Fix login",
+            tool: "claude",
+        };
+        assert_eq!(
+            render(DEFAULT_BRANCH_TEMPLATE, &vars).unwrap(),
+            "aicloner-issue12"
+        );
+        assert_eq!(
+            render("feat/{issue}-{slug}", &vars).unwrap(),
+            "feat/12-fix-login"
+        );
+        assert_eq!(render("{tool}/{issue}", &vars).unwrap(), "claude/12");
+        assert_eq!(render("{date}-{issue}", &vars).unwrap().len(), 11);
+
+        assert!(validate_template(DEFAULT_BRANCH_TEMPLATE).is_ok());
+        assert!(validate_template("feat/{issue}-{slug}").is_ok());
+        assert!(validate_template("feat/{slug}").is_err());
+        assert!(validate_template("feat/{issue}-{title}").is_err());
+        assert!(validate_template("feat/{issue").is_err());
+        assert!(validate_template("feat..{issue}").is_err());
+        assert!(validate_template("feat {issue}").is_err());
+        assert_eq!(task_name("feat/12-fix-login"), "feat-12-fix-login");
+    }
+
+    #[test]
+    fn test_matches_issue() {
+        assert!(matches_issue(DEFAULT_BRANCH_TEMPLATE, "aicloner-issue1", 1));
+        assert!(matches_issue(
+            DEFAULT_BRANCH_TEMPLATE,
+            "aicloner-issue1-2",
+            1
+        ));
+        assert!(!matches_issue(
+            DEFAULT_BRANCH_TEMPLATE,
+            "aicloner-issue12",
+            1
+        ));
+        assert!(matches_issue(
+            "feat/{issue}-{slug}",
+            "feat/12-fix-login",
+            12
+        ));
+        assert!(matches_issue(
+            "feat/{issue}-{slug}",
+            "feat/12-fix-login-3",
+            12
+        ));
+        assert!(!matches_issue("feat/{issue}-{slug}", "feat/12", 12));
+        assert!(!matches_issue("feat/{issue}-{slug}", "feat/123-fix", 12));
+    }
+}
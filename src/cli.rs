@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use clap::{Args, Parser, Subcommand};
 
-const DEFAULT_CONFIG: &str = ".aicloner.toml";
+use crate::{
+    config::{SyncMethod, WorkspaceStrategy, CONFIG_FILE},
+    issue::{IssueQuery, IssueState},
+    output::OutputFormat,
+    prompt::{DEFAULT_TEMPLATE, RESUME_TEMPLATE},
+    start::ConflictPolicy,
+};
 
 #[derive(Debug, Parser)]
 #[command(
@@ -11,6 +17,17 @@ const DEFAULT_CONFIG: &str = ".aicloner.toml";
     about = "Task ごとに git clone を管理するツール"
 )]
 pub struct Cli {
+    /// Fail fast instead of running network commands
+    #[arg(long = "offline", global = true)]
+    pub offline: bool,
+    /// Never prompt: answer confirmations with yes and take choices from flags
+    #[arg(
+        short = 'y',
+        long = "non-interactive",
+        visible_alias = "yes",
+        global = true
+    )]
+    pub non_interactive: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -22,7 +39,24 @@ pub enum Commands {
     Rm(RmArgs),
     List(ListArgs),
     Start(StartArgs),
+    Resume(ResumeArgs),
     Issues(IssuesArgs),
+    Repack(RepackArgs),
+    Fetch(FetchArgs),
+    Sync(SyncArgs),
+    Doctor(DoctorArgs),
+    Status(StatusArgs),
+    Prune(PruneArgs),
+    Logs(LogsArgs),
+    /// Inspect the prompt templates used by start
+    #[command(subcommand)]
+    Prompt(PromptCommands),
+    /// Manage the user-level list of aicloner repositories
+    #[command(subcommand)]
+    Repos(ReposCommands),
+    /// Inspect and edit the repository config file
+    #[command(subcommand)]
+    Config(ConfigCommands),
 }
 
 #[derive(Debug, Args)]
@@ -33,65 +67,420 @@ pub struct InitArgs {
     pub base_dir: String,
     #[arg(long = "workspaces-dir", default_value = "ws", value_name = "PATH")]
     pub workspaces_dir: String,
-    #[arg(long = "config", default_value = DEFAULT_CONFIG, value_name = "PATH")]
+    /// Default workspace strategy stored in the config
+    #[arg(long = "strategy", value_enum, default_value_t = WorkspaceStrategy::Clone)]
+    pub strategy: WorkspaceStrategy,
+    /// Base branch to clone (defaults to the remote's default branch)
+    #[arg(long = "branch", value_name = "BRANCH")]
+    pub branch: Option<String>,
+    #[arg(long = "config", default_value = CONFIG_FILE, value_name = "PATH")]
     pub config: PathBuf,
 }
 
 #[derive(Debug, Args)]
 pub struct AddArgs {
     pub task_name: String,
-    #[arg(long = "from", default_value = "main", value_name = "BRANCH")]
-    pub base_branch: String,
-    #[arg(long = "config", default_value = DEFAULT_CONFIG, value_name = "PATH")]
-    pub config: PathBuf,
+    /// Base branch (defaults to default_branch from the config)
+    #[arg(long = "from", value_name = "BRANCH")]
+    pub base_branch: Option<String>,
+    /// Override the workspace strategy from the config
+    #[arg(long = "strategy", value_enum)]
+    pub strategy: Option<WorkspaceStrategy>,
+    /// Copy shared objects into the new clone instead of keeping alternates
+    #[arg(long = "dissociate")]
+    pub dissociate: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
 pub struct RmArgs {
     pub task_name: String,
-    #[arg(long = "config", default_value = DEFAULT_CONFIG, value_name = "PATH")]
-    pub config: PathBuf,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Remove even if uncommitted, stashed or unpushed work would be lost
     #[arg(long = "force", default_value_t = false)]
     pub force: bool,
+    /// Save unpushed commits and uncommitted changes under .aicloner/trash before removing
+    #[arg(long = "archive")]
+    pub archive: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct ListArgs {
-    #[arg(long = "config", default_value = DEFAULT_CONFIG, value_name = "PATH")]
-    pub config: PathBuf,
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+    /// Aggregate over every repository in the user-level registry
+    #[arg(long = "all-repos", conflicts_with = "config")]
+    pub all_repos: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
 pub struct StartArgs {
-    #[arg(value_name = "ISSUE_NUMBER")]
-    pub issue_number: u32,
-    #[arg(long = "config", default_value = DEFAULT_CONFIG, value_name = "PATH")]
-    pub config: PathBuf,
-    /// Use Claude CLI (default)
+    /// Issues to start (more than one requires --headless)
+    #[arg(value_name = "ISSUE_NUMBER", required_unless_present = "labels")]
+    pub issue_numbers: Vec<u32>,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Use Claude CLI (shorthand for --tool claude)
     #[arg(long = "claude", group = "ai_tool")]
     pub use_claude: bool,
-    /// Use Codex CLI
+    /// Use Codex CLI (shorthand for --tool codex)
     #[arg(long = "codex", group = "ai_tool")]
     pub use_codex: bool,
+    /// AI tool defined in the built-in, user or repository tool registry
+    #[arg(long = "tool", value_name = "NAME", group = "ai_tool")]
+    pub tool: Option<String>,
     /// Start with plan mode
     #[arg(long = "plan")]
     pub plan: bool,
+    /// Prompt template name (.aicloner/prompts/<NAME>.md)
+    #[arg(long = "template", value_name = "NAME", default_value = DEFAULT_TEMPLATE)]
+    pub template: String,
+    /// Override the workspace strategy from the config
+    #[arg(long = "strategy", value_enum)]
+    pub strategy: Option<WorkspaceStrategy>,
+    /// Copy shared objects into the new clone instead of keeping alternates
+    #[arg(long = "dissociate")]
+    pub dissociate: bool,
+    /// Run the AI tool under a pseudo-terminal and record the session to .aicloner/logs/
+    #[arg(long = "record", conflicts_with = "headless")]
+    pub record: bool,
+    /// Base branch to cut the workspace from (defaults to default_branch)
+    #[arg(long = "from", value_name = "BRANCH")]
+    pub from: Option<String>,
+    /// Branch name to create instead of the one from branch_template
+    #[arg(long = "branch", value_name = "NAME", conflicts_with = "headless")]
+    pub branch: Option<String>,
+    /// What to do when the branch already exists (asks when omitted)
+    #[arg(
+        long = "on-conflict",
+        value_enum,
+        value_name = "POLICY",
+        conflicts_with = "headless"
+    )]
+    pub on_conflict: Option<ConflictPolicy>,
+    /// Run the AI tool non-interactively with output captured to .aicloner/logs/
+    #[arg(long = "headless")]
+    pub headless: bool,
+    /// Also start every open issue with this label (repeatable; all must match)
+    #[arg(long = "label", value_name = "LABEL", requires = "headless")]
+    pub labels: Vec<String>,
+    /// Maximum number of headless sessions running at once
+    #[arg(
+        long = "max-parallel",
+        value_name = "N",
+        default_value_t = 4,
+        requires = "headless"
+    )]
+    pub max_parallel: usize,
 }
 
 impl StartArgs {
-    /// Returns the selected AI tool (defaults to Claude)
-    pub fn selected_tool(&self) -> crate::ai_tool::AiTool {
+    /// Returns the selected AI tool name (None selects the configured default)
+    pub fn selected_tool(&self) -> Option<&str> {
         if self.use_codex {
-            crate::ai_tool::AiTool::Codex
+            Some("codex")
+        } else if self.use_claude {
+            Some("claude")
         } else {
-            // Default to Claude (whether --claude is specified or not)
-            crate::ai_tool::AiTool::Claude
+            self.tool.as_deref()
         }
     }
 }
 
+#[derive(Debug, Args)]
+pub struct ResumeArgs {
+    /// Task name or issue number (e.g. 12 or #12; defaults to the current workspace)
+    #[arg(value_name = "TASK|ISSUE")]
+    pub target: Option<String>,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// AI tool to open (defaults to the tool recorded for the task)
+    #[arg(long = "tool", value_name = "NAME")]
+    pub tool: Option<String>,
+    /// Resume with plan mode
+    #[arg(long = "plan")]
+    pub plan: bool,
+    /// Send the resume prompt even if the tool declares resume_args
+    #[arg(long = "prompt")]
+    pub prompt: bool,
+    /// Prompt template name used when sending a prompt (.aicloner/prompts/<NAME>.md)
+    #[arg(long = "template", value_name = "NAME", default_value = RESUME_TEMPLATE)]
+    pub template: String,
+    /// Run the AI tool under a pseudo-terminal and record the session to .aicloner/logs/
+    #[arg(long = "record")]
+    pub record: bool,
+}
+
 #[derive(Debug, Args)]
 pub struct IssuesArgs {
-    #[arg(long = "config", default_value = DEFAULT_CONFIG, value_name = "PATH")]
-    pub config: PathBuf,
+    /// Issue state to list
+    #[arg(long = "state", value_enum, default_value_t = IssueState::Open)]
+    pub state: IssueState,
+    /// Only issues with this label (repeatable; all must match)
+    #[arg(long = "label", value_name = "LABEL")]
+    pub labels: Vec<String>,
+    /// Only issues assigned to this login (@me for yourself)
+    #[arg(long = "assignee", value_name = "LOGIN")]
+    pub assignee: Option<String>,
+    /// Only issues in this milestone
+    #[arg(long = "milestone", value_name = "TITLE")]
+    pub milestone: Option<String>,
+    /// Search query passed to the forge
+    #[arg(long = "search", value_name = "QUERY")]
+    pub search: Option<String>,
+    /// Maximum number of issues to fetch
+    #[arg(long = "limit", default_value_t = 30)]
+    pub limit: usize,
+    /// Choose an issue interactively and start working on it
+    #[arg(long = "pick", conflicts_with_all = ["format", "all_repos"])]
+    pub pick: bool,
+    /// AI tool used for the picked issue
+    #[arg(long = "tool", value_name = "NAME", requires = "pick")]
+    pub tool: Option<String>,
+    /// Start the picked issue in plan mode
+    #[arg(long = "plan", requires = "pick")]
+    pub plan: bool,
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+    /// Aggregate over every repository in the user-level registry
+    #[arg(long = "all-repos", conflicts_with = "config")]
+    pub all_repos: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+impl IssuesArgs {
+    pub fn query(&self) -> IssueQuery {
+        IssueQuery {
+            state: self.state,
+            labels: self.labels.clone(),
+            assignee: self.assignee.clone(),
+            milestone: self.milestone.clone(),
+            search: self.search.clone(),
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct RepackArgs {
+    /// Tasks to make self-contained
+    #[arg(value_name = "TASK", required_unless_present = "all")]
+    pub task_names: Vec<String>,
+    /// Repack every task
+    #[arg(long = "all", conflicts_with = "task_names")]
+    pub all: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct FetchArgs {
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SyncArgs {
+    /// Tasks to update
+    #[arg(value_name = "TASK", required_unless_present = "all")]
+    pub task_names: Vec<String>,
+    /// Update every task
+    #[arg(long = "all", conflicts_with = "task_names")]
+    pub all: bool,
+    /// Override the sync method from the config
+    #[arg(long = "method", value_enum)]
+    pub method: Option<SyncMethod>,
+    /// Stash uncommitted changes before updating instead of skipping the task
+    #[arg(long = "autostash")]
+    pub autostash: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Reconcile the state file with the workspace directories
+    #[arg(long = "fix")]
+    pub fix: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// Tasks to inspect (defaults to the current workspace, or every task outside one)
+    #[arg(value_name = "TASK", conflicts_with = "all_repos")]
+    pub task_names: Vec<String>,
+    /// Skip looking up pull requests on the forge
+    #[arg(long = "no-pr")]
+    pub no_pr: bool,
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+    /// Aggregate over every repository in the user-level registry
+    #[arg(long = "all-repos", conflicts_with = "config")]
+    pub all_repos: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct PruneArgs {
+    /// Only show the tasks that would be removed, without fetching or writing anything
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+    /// Only consider tasks created at least this long ago (e.g. 14d, 12h)
+    #[arg(long = "older-than", value_name = "DURATION", value_parser = humantime::parse_duration)]
+    pub older_than: Option<Duration>,
+    /// Skip looking up pull requests and issues on the forge
+    #[arg(long = "no-pr")]
+    pub no_pr: bool,
+    /// Save unpushed commits and uncommitted changes under .aicloner/trash before removing
+    #[arg(long = "archive")]
+    pub archive: bool,
+    /// Aggregate over every repository in the user-level registry
+    #[arg(long = "all-repos", conflicts_with = "config")]
+    pub all_repos: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct LogsArgs {
+    pub task_name: String,
+    /// Keep printing the latest log until the session finishes
+    #[arg(long = "follow", short = 'f')]
+    pub follow: bool,
+    /// List the recorded sessions instead of printing the latest one
+    #[arg(long = "list", conflicts_with = "follow")]
+    pub list: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommands {
+    /// Report unknown keys, invalid values and pending migrations
+    Check(ConfigCheckArgs),
+    /// Print the effective value of a key (e.g. base_dir, prompt.max_chars)
+    Get(ConfigGetArgs),
+    /// Set a key in the repository config, keeping comments and ordering
+    Set(ConfigSetArgs),
+    /// Remove a key from the repository config
+    Unset(ConfigUnsetArgs),
+    /// Open the repository config in $VISUAL / $EDITOR and validate it on save
+    Edit(ConfigEditArgs),
+    /// List every effective key from built-in defaults, user config and repository config
+    List(ConfigListArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigCheckArgs {
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigGetArgs {
+    #[arg(value_name = "KEY")]
+    pub key: String,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigSetArgs {
+    #[arg(value_name = "KEY")]
+    pub key: String,
+    /// Value written as TOML when it parses as one (true, 10, ["a"]), otherwise as a string
+    #[arg(value_name = "VALUE", allow_hyphen_values = true)]
+    pub value: String,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigUnsetArgs {
+    #[arg(value_name = "KEY")]
+    pub key: String,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigEditArgs {
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigListArgs {
+    /// Show which layer (default, user config or repository config) defines each value
+    #[arg(long = "show-origin")]
+    pub show_origin: bool,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ReposCommands {
+    /// List registered repositories with their task counts
+    List(ReposListArgs),
+    /// Register an existing aicloner repository
+    Add(ReposAddArgs),
+    /// Remove a repository from the list (its files are kept)
+    Forget(ReposForgetArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ReposListArgs {
+    /// Output format
+    #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Args)]
+pub struct ReposAddArgs {
+    /// Config file or the directory containing it (defaults to the discovered config)
+    #[arg(value_name = "PATH")]
+    pub path: Option<PathBuf>,
+    /// Name shown in the REPO column (defaults to the directory name)
+    #[arg(long = "name", value_name = "NAME")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReposForgetArgs {
+    #[arg(value_name = "NAME")]
+    pub name: String,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PromptCommands {
+    /// Print the prompt that start would send for an issue
+    Render(PromptRenderArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct PromptRenderArgs {
+    #[arg(value_name = "ISSUE_NUMBER")]
+    pub issue_number: u32,
+    /// Render the plan mode variant
+    #[arg(long = "plan")]
+    pub plan: bool,
+    /// AI tool exposed to the template as `tool`
+    #[arg(long = "tool", value_name = "NAME")]
+    pub tool: Option<String>,
+    /// Prompt template name (.aicloner/prompts/<NAME>.md)
+    #[arg(long = "template", value_name = "NAME", default_value = DEFAULT_TEMPLATE)]
+    pub template: String,
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<PathBuf>,
 }
@@ -1,15 +1,169 @@
-use std::{fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    ai_tool::ToolSpec,
+    branch::DEFAULT_BRANCH_TEMPLATE,
+    config_schema::{self, CONFIG_VERSION},
+    forge::ForgeKind,
+};
+
+/// ユーザー単位の設定ディレクトリを上書きする環境変数
+pub const CONFIG_HOME_ENV: &str = "AICLONER_CONFIG_HOME";
+/// リポジトリの設定ファイルを指定する環境変数
+pub const CONFIG_ENV: &str = "AICLONER_CONFIG";
+/// リポジトリの設定ファイル名
+pub const CONFIG_FILE: &str = ".aicloner.toml";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// 設定ファイルの形式の version (無ければ 0 とみなし、読み込み時に移行する)
+    #[serde(default)]
+    pub version: u32,
     pub repo_url: String,
     #[serde(default = "default_base_dir")]
     pub base_dir: String,
     #[serde(default = "default_workspaces_dir")]
     pub workspaces_dir: String,
+    #[serde(default)]
+    pub workspace_strategy: WorkspaceStrategy,
+    /// clone 方式で base のオブジェクトを `--reference-if-able` で共有する
+    #[serde(default = "default_share_objects")]
+    pub share_objects: bool,
+    /// 共有したオブジェクトを clone 直後に取り込み、alternates を残さない
+    #[serde(default)]
+    pub dissociate: bool,
+    /// リモートの `--mirror` キャッシュを置くディレクトリ
+    #[serde(default = "default_mirror_dir")]
+    pub mirror_dir: String,
+    /// タスク作成前にミラーを自動で更新する
+    #[serde(default = "default_auto_fetch")]
+    pub auto_fetch: bool,
+    /// sync でタスクをベースブランチに追従させる方法
+    #[serde(default)]
+    pub sync_method: SyncMethod,
+    /// `start` で常にセッションを `.aicloner/logs/` に記録する
+    #[serde(default)]
+    pub record_sessions: bool,
+    /// タスクのベースにするブランチ (`init` でリモートの既定ブランチを記録する)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<String>,
+    /// `start` で作成するブランチ名 (`{issue}` `{slug}` `{user}` `{tool}` `{date}` を展開する)
+    #[serde(default = "default_branch_template")]
+    pub branch_template: String,
+    /// Issue と PR を扱うサービス (省略時は `repo_url` から判定する)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge: Option<ForgeKind>,
+    /// `forge = "fixture"` で読み込むファイル (省略時は `.aicloner/forge-fixture.toml`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forge_fixture: Option<String>,
+    /// `start` で使うツール (省略時はユーザー設定、それも無ければ claude)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_tool: Option<String>,
+    /// AI ツールの定義 (同名の組み込み・ユーザー設定の定義を置き換える)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, ToolSpec>,
+    /// プロンプトに埋め込む Issue の量
+    #[serde(default, skip_serializing_if = "PromptConfig::is_default")]
+    pub prompt: PromptConfig,
+}
+
+/// `start` のプロンプトに埋め込む Issue の量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptConfig {
+    /// 埋め込むコメントの最大件数 (超えた分は古いものから省略する)
+    pub max_comments: usize,
+    /// 本文とコメント 1 件あたりの最大文字数
+    pub max_chars: usize,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        Self {
+            max_comments: 20,
+            max_chars: 4000,
+        }
+    }
+}
+
+impl PromptConfig {
+    fn is_default(&self) -> bool {
+        *self == PromptConfig::default()
+    }
+}
+
+/// ユーザー単位の設定 (`~/.config/aicloner/config.toml`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_tool: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tools: BTreeMap<String, ToolSpec>,
+}
+
+/// タスク用ワークスペースの作成方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceStrategy {
+    /// リモートからタスクごとに独立した clone を作成する
+    #[default]
+    Clone,
+    /// base の clone から `git worktree add` でワークスペースを作成する
+    Worktree,
+}
+
+impl fmt::Display for WorkspaceStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WorkspaceStrategy::Clone => "clone",
+            WorkspaceStrategy::Worktree => "worktree",
+        };
+        f.pad(name)
+    }
+}
+
+/// sync でタスクブランチをベースブランチに追従させる方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncMethod {
+    /// ベースブランチの先頭へ rebase する
+    #[default]
+    Rebase,
+    /// ベースブランチを merge する
+    Merge,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            repo_url: String::new(),
+            base_dir: default_base_dir(),
+            workspaces_dir: default_workspaces_dir(),
+            workspace_strategy: WorkspaceStrategy::default(),
+            share_objects: default_share_objects(),
+            dissociate: false,
+            mirror_dir: default_mirror_dir(),
+            auto_fetch: default_auto_fetch(),
+            sync_method: SyncMethod::default(),
+            record_sessions: false,
+            default_branch: None,
+            branch_template: default_branch_template(),
+            forge: None,
+            forge_fixture: None,
+            default_tool: None,
+            tools: BTreeMap::new(),
+            prompt: PromptConfig::default(),
+        }
+    }
 }
 
 impl Config {
@@ -20,8 +174,10 @@ impl Config {
         // TOML ファイルを文字列として読み込む
         let raw = fs::read_to_string(path)
             .with_context(|| format!("設定ファイルの読み込みに失敗しました: {}", path.display()))?;
-        let config = toml::from_str(&raw).context("設定ファイルのパースに失敗しました")?;
-        Ok(config)
+        let raw = config_schema::migrate_file(path, raw)?;
+        let root = path.parent().unwrap_or(Path::new(""));
+        config_schema::validate(&raw, root)
+            .with_context(|| format!("設定ファイルが不正です: {}", path.display()))
     }
 
     pub fn save(&self, path: &Path) -> Result<()> {
@@ -44,6 +200,69 @@ impl Config {
     }
 }
 
+impl UserConfig {
+    pub fn path() -> Option<PathBuf> {
+        user_config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// ユーザー設定を読み込む。存在しない場合は空の設定を返す
+    pub fn load() -> Result<UserConfig> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(UserConfig::default());
+        };
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("ユーザー設定の読み込みに失敗しました: {}", path.display()))?;
+        toml::from_str(&raw)
+            .with_context(|| format!("ユーザー設定のパースに失敗しました: {}", path.display()))
+    }
+}
+
+/// リポジトリの設定ファイルを探す
+/// `--config` > `AICLONER_CONFIG` > カレントディレクトリから親へ順に見つけた `.aicloner.toml` の順に使う
+pub fn discover_config_path(explicit: Option<&Path>) -> Result<PathBuf> {
+    let path = match explicit {
+        Some(path) => Some(path.to_path_buf()),
+        None => match env::var_os(CONFIG_ENV).filter(|path| !path.is_empty()) {
+            Some(path) => Some(PathBuf::from(path)),
+            None => find_config_upwards(&env::current_dir()?),
+        },
+    };
+    match path {
+        Some(path) if path.exists() => Ok(path),
+        path => bail!(
+            "aicloner リポジトリではありません。{} が見つかりません。\n\
+             先に 'aicloner init' を実行してください。",
+            path.map_or_else(
+                || format!("{} (親ディレクトリを含む)", CONFIG_FILE),
+                |path| path.display().to_string()
+            )
+        ),
+    }
+}
+
+/// `dir` とその親ディレクトリから `.aicloner.toml` を探す
+fn find_config_upwards(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+/// ユーザー単位の設定ディレクトリ
+/// `AICLONER_CONFIG_HOME` > `$XDG_CONFIG_HOME/aicloner` > `~/.config/aicloner` の順に探す
+pub fn user_config_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(CONFIG_HOME_ENV).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("aicloner"));
+    }
+    #[cfg(windows)]
+    if let Some(dir) = env::var_os("APPDATA").filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir).join("aicloner"));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".config").join("aicloner"))
+}
+
 fn default_base_dir() -> String {
     "base".to_string()
 }
@@ -51,3 +270,45 @@ fn default_base_dir() -> String {
 fn default_workspaces_dir() -> String {
     "ws".to_string()
 }
+
+fn default_share_objects() -> bool {
+    true
+}
+
+fn default_mirror_dir() -> String {
+    "mirror.git".to_string()
+}
+
+fn default_auto_fetch() -> bool {
+    true
+}
+
+fn default_branch_template() -> String {
+    DEFAULT_BRANCH_TEMPLATE.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_config_upwards() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let nested = tmp
+            .path()
+            .join("repo")
+            .join("ws")
+            .join("task-a")
+            .join("src");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_config_upwards(&nested), None);
+
+        let config_path = tmp.path().join("repo").join(CONFIG_FILE);
+        fs::write(&config_path, "repo_url = \"x\"\n").unwrap();
+        assert_eq!(find_config_upwards(&nested), Some(config_path.clone()));
+        assert_eq!(
+            find_config_upwards(&tmp.path().join("repo")),
+            Some(config_path)
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context, Result};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::{
+    ai_tool::{builtin_tools, DEFAULT_TOOL},
+    config::{Config, PromptConfig, UserConfig},
+    config_schema::{self, ValueType},
+    repo::confirm,
+};
+
+/// 値を定義している層
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    /// 組み込みの既定値
+    Default,
+    /// ユーザー設定 (`~/.config/aicloner/config.toml`)
+    User(PathBuf),
+    /// リポジトリの設定ファイル
+    Repo(PathBuf),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Origin::Default => f.pad("default"),
+            Origin::User(path) | Origin::Repo(path) => f.pad(&path.display().to_string()),
+        }
+    }
+}
+
+/// 重ね合わせた設定の 1 項目 (`key` は `prompt.max_chars` のようにドットでつなぐ)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub value: toml::Value,
+    pub origin: Origin,
+}
+
+impl Entry {
+    /// 文字列は引用符を付けず、それ以外は TOML の表記で返す
+    pub fn plain_value(&self) -> String {
+        match &self.value {
+            toml::Value::String(value) => value.clone(),
+            value => value.to_string(),
+        }
+    }
+}
+
+/// 組み込みの既定値・ユーザー設定・リポジトリ設定の順に重ねた設定の全項目
+pub fn layered_entries(config_path: &Path) -> Result<Vec<Entry>> {
+    let user_path = UserConfig::path().filter(|path| path.exists());
+    merge_layers(user_path, config_path)
+}
+
+/// ツールの定義は、ツール定義の読み込みと同じく後の層で丸ごと置き換える
+fn merge_layers(user_path: Option<PathBuf>, config_path: &Path) -> Result<Vec<Entry>> {
+    let mut layers = vec![(defaults_table()?, Origin::Default)];
+    if let Some(path) = user_path {
+        layers.push((read_table(&path)?, Origin::User(path)));
+    }
+    layers.push((
+        read_table(config_path)?,
+        Origin::Repo(config_path.to_path_buf()),
+    ));
+
+    let mut merged: BTreeMap<String, Entry> = BTreeMap::new();
+    for (table, origin) in layers {
+        if let Some(tools) = table.get("tools").and_then(|tools| tools.as_table()) {
+            for name in tools.keys() {
+                let prefix = format!("tools.{}.", name);
+                merged.retain(|key, _| !key.starts_with(&prefix));
+            }
+        }
+        let mut values = Vec::new();
+        flatten("", &table, &mut values);
+        for (key, value) in values {
+            let entry = Entry {
+                key: key.clone(),
+                value,
+                origin: origin.clone(),
+            };
+            merged.insert(key, entry);
+        }
+    }
+    Ok(merged.into_values().collect())
+}
+
+/// `key` とその下の項目 (`tools.claude` なら `tools.claude.*`)
+pub fn get(config_path: &Path, key: &str) -> Result<Vec<Entry>> {
+    config_schema::check_key_path(key)?;
+    let prefix = format!("{}.", key);
+    let entries: Vec<Entry> = layered_entries(config_path)?
+        .into_iter()
+        .filter(|entry| entry.key == key || entry.key.starts_with(&prefix))
+        .collect();
+    if entries.is_empty() {
+        bail!("キー {} は設定されていません", key);
+    }
+    Ok(entries)
+}
+
+/// リポジトリの設定ファイルの `key` を `value` にする (コメントや順序は保つ)
+/// 文字列のキーはそのまま文字列として、それ以外は TOML として読んでキーの型と照らして書き込む
+pub fn set(config_path: &Path, key: &str, value: &str) -> Result<()> {
+    let value_type = config_schema::check_key_path(key)?;
+    let mut doc = read_document(config_path)?;
+    let (parents, leaf) = split_key(key);
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for (depth, part) in parents.iter().enumerate() {
+        if table.get(part).is_none() {
+            let mut child = toml_edit::Table::new();
+            child.set_implicit(true);
+            table.insert(part, Item::Table(child));
+        }
+        table = table
+            .get_mut(part)
+            .and_then(Item::as_table_like_mut)
+            .with_context(|| format!("{} はテーブルではありません", parents[..=depth].join(".")))?;
+    }
+
+    let mut new_value = match value_type {
+        ValueType::String => toml_edit::Value::from(value),
+        _ => match value.parse::<toml_edit::Value>() {
+            Ok(parsed) if value_type.accepts(&parsed) => parsed,
+            _ => bail!("{} には{}を指定してください: {}", key, value_type, value),
+        },
+    };
+    let current = table.get(leaf).and_then(Item::as_value);
+    // 行末のコメントなど、元の値の前後の書式を引き継ぐ
+    if let Some(current) = current {
+        *new_value.decor_mut() = current.decor().clone();
+    }
+    table.insert(leaf, Item::Value(new_value));
+    save_document(config_path, &doc)
+}
+
+/// リポジトリの設定ファイルから `key` を取り除く (空になったテーブルも取り除く)
+pub fn unset(config_path: &Path, key: &str) -> Result<()> {
+    config_schema::check_key_path(key)?;
+    let mut doc = read_document(config_path)?;
+    let parts: Vec<&str> = key.split('.').collect();
+    if remove_path(doc.as_table_mut(), &parts).is_none() {
+        bail!(
+            "キー {} は {} に書かれていません",
+            key,
+            config_path.display()
+        );
+    }
+    save_document(config_path, &doc)
+}
+
+/// `$VISUAL` / `$EDITOR` で設定ファイルを編集する
+/// 編集は一時ファイルで行い、検証に通ったときだけ設定ファイルを置き換える
+pub fn edit(config_path: &Path) -> Result<()> {
+    let original = fs::read_to_string(config_path).with_context(|| {
+        format!(
+            "設定ファイルの読み込みに失敗しました: {}",
+            config_path.display()
+        )
+    })?;
+    let draft = config_path.with_extension("edit.toml");
+    fs::write(&draft, &original)
+        .with_context(|| format!("一時ファイルの作成に失敗しました: {}", draft.display()))?;
+    let result = edit_draft(config_path, &draft, &original);
+    if draft.exists() {
+        let _ = fs::remove_file(&draft);
+    }
+    result
+}
+
+fn edit_draft(config_path: &Path, draft: &Path, original: &str) -> Result<()> {
+    loop {
+        run_editor(draft)?;
+        let edited = fs::read_to_string(draft)
+            .with_context(|| format!("{} の読み込みに失敗しました", draft.display()))?;
+        if edited == original {
+            println!("変更はありません。");
+            return Ok(());
+        }
+        match check_content(&edited, config_path) {
+            Ok(()) => {
+                fs::rename(draft, config_path).with_context(|| {
+                    format!(
+                        "設定ファイルの書き込みに失敗しました: {}",
+                        config_path.display()
+                    )
+                })?;
+                println!("✓ 設定ファイルを保存しました: {}", config_path.display());
+                return Ok(());
+            }
+            Err(err) => {
+                eprintln!("設定ファイルが不正です: {:#}", err);
+                if !io::stdin().is_terminal() || !confirm("もう一度編集しますか? [y/N]: ")?
+                {
+                    bail!(
+                        "変更を破棄しました (設定ファイルは元のままです): {}",
+                        config_path.display()
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// 編集後の内容を検証する (古い version のままでも、移行後の内容で確かめる)
+fn check_content(raw: &str, config_path: &Path) -> Result<()> {
+    let migrated = config_schema::migrate(raw)?.map(|(_, migrated)| migrated);
+    let raw = migrated.as_deref().unwrap_or(raw);
+    config_schema::validate(raw, config_path.parent().unwrap_or(Path::new("")))?;
+    Ok(())
+}
+
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .ok()
+        .or_else(|| env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| default_editor().to_string());
+    // `code --wait` のような引数付きの指定も受け付ける
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(default_editor());
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("エディタを起動できませんでした: {}", editor))?;
+    if !status.success() {
+        bail!("エディタが異常終了しました: {}", editor);
+    }
+    Ok(())
+}
+
+fn default_editor() -> &'static str {
+    if cfg!(windows) {
+        "notepad"
+    } else {
+        "vi"
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("設定ファイルの読み込みに失敗しました: {}", path.display()))?;
+    toml::from_str(&raw)
+        .with_context(|| format!("設定ファイルのパースに失敗しました: {}", path.display()))
+}
+
+fn read_document(path: &Path) -> Result<DocumentMut> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("設定ファイルの読み込みに失敗しました: {}", path.display()))?;
+    raw.parse()
+        .with_context(|| format!("設定ファイルのパースに失敗しました: {}", path.display()))
+}
+
+/// 変更後の内容を検証してから書き込む
+fn save_document(path: &Path, doc: &DocumentMut) -> Result<()> {
+    let content = doc.to_string();
+    check_content(&content, path).context("変更後の設定が不正なため、書き込みませんでした")?;
+    fs::write(path, content)
+        .with_context(|| format!("設定ファイルの書き込みに失敗しました: {}", path.display()))
+}
+
+/// `tools.stub.executable` を (`["tools", "stub"]`, `"executable"`) に分ける
+fn split_key(key: &str) -> (Vec<&str>, &str) {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let leaf = parts.pop().unwrap_or(key);
+    (parts, leaf)
+}
+
+fn remove_path(table: &mut dyn TableLike, parts: &[&str]) -> Option<Item> {
+    let (first, rest) = parts.split_first()?;
+    if rest.is_empty() {
+        return table.remove(first);
+    }
+    let child = table.get_mut(first)?.as_table_like_mut()?;
+    let removed = remove_path(child, rest)?;
+    if child.is_empty() {
+        table.remove(first);
+    }
+    Some(removed)
+}
+
+/// 組み込みの既定値 (`repo_url` のように既定値の無いキーは含めない)
+fn defaults_table() -> Result<toml::Table> {
+    let mut defaults =
+        toml::Table::try_from(Config::default()).context("既定値の取得に失敗しました")?;
+    defaults.remove("repo_url");
+    defaults.insert("default_tool".to_string(), DEFAULT_TOOL.into());
+    defaults.insert(
+        "tools".to_string(),
+        toml::Value::try_from(builtin_tools()).context("既定値の取得に失敗しました")?,
+    );
+    defaults.insert(
+        "prompt".to_string(),
+        toml::Value::try_from(PromptConfig::default()).context("既定値の取得に失敗しました")?,
+    );
+    Ok(defaults)
+}
+
+fn flatten(prefix: &str, table: &toml::Table, out: &mut Vec<(String, toml::Value)>) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(child) => flatten(&format!("{}.", key), child, out),
+            value => out.push((key, value.clone())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_unset_keep_comments() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(".aicloner.toml");
+        fs::write(
+            &path,
+            "version = 1\n# remote\nrepo_url = \"x\"\nbase_dir = \"base\" # 共有\n",
+        )
+        .unwrap();
+
+        set(&path, "base_dir", "main-base").unwrap();
+        set(&path, "share_objects", "false").unwrap();
+        set(&path, "default_branch", "develop").unwrap();
+        set(&path, "prompt.max_chars", "100").unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(
+            raw.starts_with(
+                "version = 1\n# remote\nrepo_url = \"x\"\nbase_dir = \"main-base\" # 共有\n\
+                 share_objects = false\ndefault_branch = \"develop\"\n"
+            ),
+            "{}",
+            raw
+        );
+        assert!(raw.ends_with("[prompt]\nmax_chars = 100\n"), "{}", raw);
+
+        assert!(set(&path, "workspace_dir", "w").is_err());
+        assert!(set(&path, "workspaces_dir", "main-base/ws").is_err());
+        assert!(set(&path, "prompt.max_chars", "many").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), raw);
+
+        unset(&path, "prompt.max_chars").unwrap();
+        unset(&path, "default_branch").unwrap();
+        assert!(unset(&path, "default_branch").is_err());
+        assert!(unset(&path, "repo_url").is_err());
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(
+            !raw.contains("prompt") && !raw.contains("develop"),
+            "{}",
+            raw
+        );
+    }
+
+    #[test]
+    fn test_set_uses_the_key_type() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(".aicloner.toml");
+        fs::write(&path, "version = 1\nrepo_url = \"x\"\n").unwrap();
+
+        // 文字列のキーは TOML として読める値でも文字列のまま書き込む
+        set(&path, "default_branch", "2024").unwrap();
+        set(&path, "tools.stub.env.DEBUG", "true").unwrap();
+        set(&path, "record_sessions", "true").unwrap();
+        let raw = fs::read_to_string(&path).unwrap();
+        assert!(raw.contains("default_branch = \"2024\"\n"), "{}", raw);
+        assert!(raw.contains("DEBUG = \"true\"\n"), "{}", raw);
+        assert!(raw.contains("record_sessions = true\n"), "{}", raw);
+
+        let err = set(&path, "share_objects", "yes").unwrap_err();
+        assert!(err.to_string().contains("真偽値"), "{}", err);
+        assert!(set(&path, "tools.stub.args", "--fast").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), raw);
+    }
+
+    #[test]
+    fn test_layered_entries() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join(".aicloner.toml");
+        fs::write(
+            &path,
+            "repo_url = \"x\"\nbase_dir = \"b\"\n\n[tools.claude]\nexecutable = \"my-claude\"\n",
+        )
+        .unwrap();
+        let entries = merge_layers(None, &path).unwrap();
+        let find = |key: &str| entries.iter().find(|entry| entry.key == key);
+
+        let base = find("base_dir").unwrap();
+        assert_eq!(base.plain_value(), "b");
+        assert_eq!(base.origin, Origin::Repo(path.clone()));
+        assert_eq!(find("workspaces_dir").unwrap().origin, Origin::Default);
+        assert_eq!(find("prompt.max_chars").unwrap().plain_value(), "4000");
+        // 同名のツールは丸ごと置き換える
+        assert_eq!(
+            find("tools.claude.executable").unwrap().plain_value(),
+            "my-claude"
+        );
+        assert!(find("tools.claude.display_name").is_none());
+        assert_eq!(
+            find("tools.codex.executable").unwrap().origin,
+            Origin::Default
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use toml_edit::DocumentMut;
+
+use crate::{branch, config::Config, forge};
+
+/// 設定ファイルの現在の version
+/// キーの改名など、古い設定ファイルの書き換えが必要な変更をしたときに上げて `MIGRATIONS` に移行処理を足す
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` は version n の設定を version n + 1 の形に書き換える
+const MIGRATIONS: [fn(&mut DocumentMut); CONFIG_VERSION as usize] = [migrate_v0];
+
+/// `.aicloner.toml` の最上位のキーと値の型
+const CONFIG_KEYS: &[(&str, ValueType)] = &[
+    ("version", ValueType::Integer),
+    ("repo_url", ValueType::String),
+    ("base_dir", ValueType::String),
+    ("workspaces_dir", ValueType::String),
+    ("workspace_strategy", ValueType::String),
+    ("share_objects", ValueType::Bool),
+    ("dissociate", ValueType::Bool),
+    ("mirror_dir", ValueType::String),
+    ("auto_fetch", ValueType::Bool),
+    ("sync_method", ValueType::String),
+    ("record_sessions", ValueType::Bool),
+    ("default_branch", ValueType::String),
+    ("branch_template", ValueType::String),
+    ("forge", ValueType::String),
+    ("forge_fixture", ValueType::String),
+    ("default_tool", ValueType::String),
+    ("tools", ValueType::Table),
+    ("prompt", ValueType::Table),
+];
+/// `[prompt]` のキーと値の型
+const PROMPT_KEYS: &[(&str, ValueType)] = &[
+    ("max_comments", ValueType::Integer),
+    ("max_chars", ValueType::Integer),
+];
+/// `[tools.<name>]` のキーと値の型
+const TOOL_KEYS: &[(&str, ValueType)] = &[
+    ("display_name", ValueType::String),
+    ("executable", ValueType::String),
+    ("version_args", ValueType::Array),
+    ("args", ValueType::Array),
+    ("prompt", ValueType::String),
+    ("prompt_flag", ValueType::String),
+    ("plan_args", ValueType::Array),
+    ("headless_args", ValueType::Array),
+    ("resume_args", ValueType::Array),
+    ("env", ValueType::Table),
+    ("windows_script", ValueType::String),
+];
+/// 候補として示すキーとの類似度の下限 (Jaro-Winkler)
+const SUGGESTION_THRESHOLD: f64 = 0.8;
+
+/// 設定ファイルの問題
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// 知らないキー (綴りの近いキーがあれば `suggestion`)
+    UnknownKey {
+        key: String,
+        suggestion: Option<String>,
+    },
+    /// 値が使えない
+    InvalidValue { key: &'static str, message: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnknownKey {
+                key,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "不明なキー {} ({} の誤りではありませんか?)",
+                key, suggestion
+            ),
+            Problem::UnknownKey {
+                key,
+                suggestion: None,
+            } => write!(f, "不明なキー {}", key),
+            Problem::InvalidValue { key, message } => write!(f, "{}: {}", key, message),
+        }
+    }
+}
+
+/// 設定値の型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ValueType {
+    String,
+    Bool,
+    Integer,
+    Array,
+    Table,
+}
+
+impl ValueType {
+    /// `value` がこの型か
+    pub fn accepts(self, value: &toml_edit::Value) -> bool {
+        match self {
+            ValueType::String => value.is_str(),
+            ValueType::Bool => value.is_bool(),
+            ValueType::Integer => value.is_integer(),
+            ValueType::Array => value.is_array(),
+            ValueType::Table => value.is_inline_table(),
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ValueType::String => "文字列",
+            ValueType::Bool => "真偽値",
+            ValueType::Integer => "整数",
+            ValueType::Array => "配列",
+            ValueType::Table => "テーブル",
+        };
+        f.pad(label)
+    }
+}
+
+/// 古い version の設定を現在の形に書き換え、元の version と書き換えた内容を返す
+/// 既に現在の version なら None を返す
+pub fn migrate(raw: &str) -> Result<Option<(u32, String)>> {
+    let mut doc: DocumentMut = raw.parse().context("設定ファイルのパースに失敗しました")?;
+    let version = match doc.get("version") {
+        None => 0,
+        Some(item) => match item.as_integer().and_then(|n| u32::try_from(n).ok()) {
+            Some(version) => version,
+            None => bail!("version は 0 以上の整数で指定してください"),
+        },
+    };
+    if version > CONFIG_VERSION {
+        bail!(
+            "version {} の設定ファイルはこの aicloner (version {} まで対応) では読めません。\
+             aicloner を更新してください。",
+            version,
+            CONFIG_VERSION
+        );
+    }
+    if version == CONFIG_VERSION {
+        return Ok(None);
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut doc);
+    }
+    doc["version"] = toml_edit::value(i64::from(CONFIG_VERSION));
+    // version を先頭に置く (他のキーの順序とコメントはそのまま)
+    doc.as_table_mut()
+        .sort_values_by(|a, _, b, _| (a.get() != "version").cmp(&(b.get() != "version")));
+    Ok(Some((version, doc.to_string())))
+}
+
+/// 古い version の設定ファイルを移行し、元の内容を `<path>.v<version>.bak` に残す
+/// 書き込めなければ警告して、移行した内容だけを返す
+pub fn migrate_file(path: &Path, raw: String) -> Result<String> {
+    let Some((version, migrated)) =
+        migrate(&raw).with_context(|| format!("設定ファイルが不正です: {}", path.display()))?
+    else {
+        return Ok(raw);
+    };
+    let backup = backup_path(path, version);
+    let written = fs::write(&backup, &raw)
+        .with_context(|| format!("バックアップの作成に失敗しました: {}", backup.display()))
+        .and_then(|_| {
+            fs::write(path, &migrated).with_context(|| {
+                format!("設定ファイルの書き込みに失敗しました: {}", path.display())
+            })
+        });
+    match written {
+        Ok(()) => eprintln!(
+            "設定ファイルを version {} から {} に移行しました: {} (元の内容: {})",
+            version,
+            CONFIG_VERSION,
+            path.display(),
+            backup.display()
+        ),
+        Err(err) => eprintln!("警告: 設定ファイルを移行できませんでした: {:#}", err),
+    }
+    Ok(migrated)
+}
+
+/// 設定ファイルを書き換えずに検査する (`config check`)
+/// 移行が必要なら元の version も返す
+pub fn check_file(path: &Path) -> Result<(Option<u32>, Vec<Problem>)> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("設定ファイルの読み込みに失敗しました: {}", path.display()))?;
+    let (version, raw) = match migrate(&raw)? {
+        Some((version, migrated)) => (Some(version), migrated),
+        None => (None, raw),
+    };
+    let (_, problems) = inspect(&raw, path.parent().unwrap_or(Path::new("")))?;
+    Ok((version, problems))
+}
+
+/// 現在の version の設定を読み込み、見つかった問題と一緒に返す
+/// 相対パスの確認には設定ファイルのあるディレクトリ `root` を使う
+pub fn inspect(raw: &str, root: &Path) -> Result<(Config, Vec<Problem>)> {
+    let table: toml::Table = toml::from_str(raw).context("設定ファイルのパースに失敗しました")?;
+    let mut problems = check_keys(&table);
+    let config: Config = toml::from_str(raw).context("設定ファイルのパースに失敗しました")?;
+    problems.extend(check_values(&config, root));
+    Ok((config, problems))
+}
+
+/// [`inspect`] で問題が見つかればすべて並べてエラーにする
+pub fn validate(raw: &str, root: &Path) -> Result<Config> {
+    let (config, problems) = inspect(raw, root)?;
+    if !problems.is_empty() {
+        let lines: Vec<String> = problems
+            .iter()
+            .map(|problem| format!("  - {}", problem))
+            .collect();
+        bail!(
+            "{} 件の問題があります:\n{}",
+            problems.len(),
+            lines.join("\n")
+        );
+    }
+    Ok(config)
+}
+
+/// `config get/set/unset` に渡された `prompt.max_chars` のようなキーを確かめ、値の型を返す
+pub fn check_key_path(key: &str) -> Result<ValueType> {
+    let parts: Vec<&str> = key.split('.').collect();
+    let (known, prefix, name): (&[(&str, ValueType)], String, &str) = match parts.as_slice() {
+        [name] => (CONFIG_KEYS, String::new(), name),
+        ["prompt", name] => (PROMPT_KEYS, "prompt.".to_string(), name),
+        ["tools", _] => return Ok(ValueType::Table),
+        ["tools", _, "env", _] => return Ok(ValueType::String),
+        ["tools", tool, name] => (TOOL_KEYS, format!("tools.{}.", tool), name),
+        _ => bail!("キー {} は設定ファイルにありません", key),
+    };
+    if name.is_empty() {
+        bail!("キー {} は設定ファイルにありません", key);
+    }
+    match known.iter().find(|(known, _)| *known == name) {
+        Some((_, value_type)) => Ok(*value_type),
+        None => {
+            let problem = Problem::UnknownKey {
+                key: key.to_string(),
+                suggestion: suggest(name, known).map(|name| format!("{}{}", prefix, name)),
+            };
+            bail!("{}", problem);
+        }
+    }
+}
+
+/// 知らないキーを探す
+fn check_keys(table: &toml::Table) -> Vec<Problem> {
+    let mut problems = unknown_keys(table, CONFIG_KEYS, "");
+    if let Some(prompt) = table.get("prompt").and_then(|value| value.as_table()) {
+        problems.extend(unknown_keys(prompt, PROMPT_KEYS, "prompt."));
+    }
+    if let Some(tools) = table.get("tools").and_then(|value| value.as_table()) {
+        for (name, tool) in tools {
+            if let Some(tool) = tool.as_table() {
+                problems.extend(unknown_keys(tool, TOOL_KEYS, &format!("tools.{}.", name)));
+            }
+        }
+    }
+    problems
+}
+
+fn unknown_keys(table: &toml::Table, known: &[(&str, ValueType)], prefix: &str) -> Vec<Problem> {
+    table
+        .keys()
+        .filter(|key| !known.iter().any(|(name, _)| name == key))
+        .map(|key| Problem::UnknownKey {
+            key: format!("{}{}", prefix, key),
+            suggestion: suggest(key, known).map(|name| format!("{}{}", prefix, name)),
+        })
+        .collect()
+}
+
+/// 綴りの最も近いキー
+fn suggest<'a>(key: &str, known: &[(&'a str, ValueType)]) -> Option<&'a str> {
+    known
+        .iter()
+        .map(|(name, _)| (strsim::jaro_winkler(key, name), *name))
+        .filter(|(score, _)| *score >= SUGGESTION_THRESHOLD)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, name)| name)
+}
+
+/// 値どうしの関係や形式を確かめる
+fn check_values(config: &Config, root: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if let Err(message) = check_repo_url(&config.repo_url) {
+        problems.push(Problem::InvalidValue {
+            key: "repo_url",
+            message,
+        });
+    }
+    if let Err(err) = branch::validate_template(&config.branch_template) {
+        problems.push(Problem::InvalidValue {
+            key: "branch_template",
+            message: format!("{:#}", err),
+        });
+    }
+
+    let dirs = [
+        ("base_dir", &config.base_dir),
+        ("workspaces_dir", &config.workspaces_dir),
+        ("mirror_dir", &config.mirror_dir),
+    ];
+    for (i, (key, dir)) in dirs.iter().enumerate() {
+        let path = normalize(&root.join(dir));
+        for (other_key, other) in &dirs[..i] {
+            let other_path = normalize(&root.join(other));
+            let message = if path == other_path {
+                format!("{} と同じディレクトリです", other_key)
+            } else if path.starts_with(&other_path) {
+                format!("{} ({}) の中にあります", other_key, other)
+            } else if other_path.starts_with(&path) {
+                format!("{} ({}) を含んでいます", other_key, other)
+            } else {
+                continue;
+            };
+            problems.push(Problem::InvalidValue { key, message });
+        }
+    }
+    problems
+}
+
+/// `repo_url` が URL (`https://host/owner/repo.git`)、scp 形式 (`git@host:owner/repo.git`)、
+/// ローカルパスのいずれかとして読めるかを確かめる
+fn check_repo_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Err("リポジトリの URL が空です".to_string());
+    }
+    if let Some((scheme, rest)) = url.split_once("://") {
+        let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid_scheme {
+            return Err(format!("URL のスキームが不正です: {}", url));
+        }
+        if !scheme.eq_ignore_ascii_case("file") && forge::url_host(url).is_none() {
+            return Err(format!("URL にホスト名がありません: {}", url));
+        }
+        if !rest.contains('/') {
+            return Err(format!("URL にリポジトリのパスがありません: {}", url));
+        }
+    }
+    let name = url
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\', ':'])
+        .next();
+    match name.map(|name| name.strip_suffix(".git").unwrap_or(name)) {
+        Some(name) if !name.is_empty() => Ok(()),
+        _ => Err(format!("URL からリポジトリ名を取り出せません: {}", url)),
+    }
+}
+
+/// `.` と `..` を取り除いたパス (ディレクトリが無くても比べられるよう、ファイルシステムは見ない)
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+/// version 0 (version の無い設定) から 1 へ
+/// キーの構成は変わっていないので、version を書き込むだけでよい
+fn migrate_v0(_doc: &mut DocumentMut) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ai_tool::ToolSpec, config::PromptConfig, forge::ForgeKind};
+
+    /// キーの一覧とその型が構造体のフィールドと食い違っていないことを確かめる
+    #[test]
+    fn test_known_keys_match_fields() {
+        let tool = ToolSpec {
+            display_name: Some("Stub".to_string()),
+            executable: "stub".to_string(),
+            prompt_flag: Some("-p".to_string()),
+            headless_args: Some(Vec::new()),
+            resume_args: Some(Vec::new()),
+            windows_script: Some("stub.js".to_string()),
+            ..ToolSpec::default()
+        };
+        let config = Config {
+            default_branch: Some("main".to_string()),
+            forge: Some(ForgeKind::Github),
+            forge_fixture: Some("forge.toml".to_string()),
+            default_tool: Some("stub".to_string()),
+            tools: [("stub".to_string(), tool)].into(),
+            prompt: PromptConfig {
+                max_comments: 1,
+                max_chars: 1,
+            },
+            ..Config::default()
+        };
+        let table = toml::Table::try_from(&config).unwrap();
+        let keys = |table: &toml::Table| {
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), value_type(value)))
+                .collect::<Vec<_>>()
+        };
+        let expected = |known: &[(&str, ValueType)]| {
+            let mut known: Vec<(String, ValueType)> = known
+                .iter()
+                .map(|(key, value_type)| (key.to_string(), *value_type))
+                .collect();
+            known.sort();
+            known
+        };
+        assert_eq!(keys(&table), expected(CONFIG_KEYS));
+        assert_eq!(
+            keys(table["prompt"].as_table().unwrap()),
+            expected(PROMPT_KEYS)
+        );
+        assert_eq!(
+            keys(table["tools"]["stub"].as_table().unwrap()),
+            expected(TOOL_KEYS)
+        );
+    }
+
+    fn value_type(value: &toml::Value) -> ValueType {
+        match value {
+            toml::Value::String(_) => ValueType::String,
+            toml::Value::Boolean(_) => ValueType::Bool,
+            toml::Value::Integer(_) => ValueType::Integer,
+            toml::Value::Array(_) => ValueType::Array,
+            toml::Value::Table(_) => ValueType::Table,
+            other => panic!("想定外の型: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_inspect_reports_problems() {
+        let raw = "version = 1\nrepo_url = \"git@github.com:o/r.git\"\nworkspace_dir = \"w\"\n\
+                   base_dir = \"ws/base\"\n\n[prompt]\nmax_coments = 3\n\n\
+                   [tools.stub]\nexecutable = \"stub\"\nargz = []\n";
+        let (_, problems) = inspect(raw, Path::new("/repo")).unwrap();
+        assert_eq!(
+            problems,
+            vec![
+                Problem::UnknownKey {
+                    key: "workspace_dir".to_string(),
+                    suggestion: Some("workspaces_dir".to_string()),
+                },
+                Problem::UnknownKey {
+                    key: "prompt.max_coments".to_string(),
+                    suggestion: Some("prompt.max_comments".to_string()),
+                },
+                Problem::UnknownKey {
+                    key: "tools.stub.argz".to_string(),
+                    suggestion: Some("tools.stub.args".to_string()),
+                },
+                Problem::InvalidValue {
+                    key: "workspaces_dir",
+                    message: "base_dir (ws/base) を含んでいます".to_string(),
+                },
+            ]
+        );
+
+        let (_, problems) = inspect("repo_url = \"x\"\n", Path::new("/repo")).unwrap();
+        assert!(problems.is_empty());
+    }
+
+    #[test]
+    fn test_check_repo_url() {
+        for url in [
+            "https://github.com/owner/repo.git",
+            "ssh://git@gitlab.example.com:2222/group/repo.git",
+            "git@github.com:owner/repo.git",
+            "file:///srv/git/repo.git",
+            "/srv/git/repo.git",
+            "../repo",
+        ] {
+            assert!(check_repo_url(url).is_ok(), "{}", url);
+        }
+        for url in [
+            "",
+            "https://",
+            "https://github.com",
+            "1http://x/y",
+            "git@host:",
+        ] {
+            assert!(check_repo_url(url).is_err(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_migrate_keeps_comments() {
+        let raw = "# aicloner\nrepo_url = \"x\" # remote\n\n[prompt]\nmax_chars = 10\n";
+        let (version, migrated) = migrate(raw).unwrap().unwrap();
+        assert_eq!(version, 0);
+        assert!(migrated.starts_with("version = 1\n"), "{}", migrated);
+        assert!(migrated.contains("# aicloner\nrepo_url = \"x\" # remote\n"));
+        assert!(migrated.contains("[prompt]\nmax_chars = 10\n"));
+        assert!(migrate(&migrated).unwrap().is_none());
+        assert!(migrate("version = 99\nrepo_url = \"x\"\n").is_err());
+        assert_eq!(
+            backup_path(Path::new("/repo/.aicloner.toml"), 0),
+            Path::new("/repo/.aicloner.toml.v0.bak")
+        );
+    }
+}
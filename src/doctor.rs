@@ -0,0 +1,126 @@
+use std::{fmt, fs, path::PathBuf};
+
+use anyhow::Result;
+
+use crate::{
+    config::WorkspaceStrategy,
+    repo::{run_command, RepoManager},
+    state::{self, TaskRecord},
+};
+
+/// 状態ファイルとワークスペースディレクトリの不整合
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// ディレクトリはあるが状態ファイルに記録が無い
+    Orphan {
+        task_name: String,
+        path: PathBuf,
+        branch: Option<String>,
+        strategy: WorkspaceStrategy,
+    },
+    /// 状態ファイルに記録があるがディレクトリが無い
+    Missing {
+        task_name: String,
+        record: TaskRecord,
+    },
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Orphan {
+                task_name, path, ..
+            } => write!(
+                f,
+                "記録の無いワークスペース: {} ({})",
+                task_name,
+                path.display()
+            ),
+            Finding::Missing { task_name, .. } => {
+                write!(f, "ディレクトリの無いタスク記録: {}", task_name)
+            }
+        }
+    }
+}
+
+/// 状態ファイルとワークスペースディレクトリを突き合わせる
+pub fn diagnose(manager: &RepoManager) -> Result<Vec<Finding>> {
+    let tasks = manager.list_tasks()?;
+    let state = manager.load_state()?;
+
+    let mut findings = Vec::new();
+    for task in &tasks {
+        if task.record.is_none() {
+            findings.push(Finding::Orphan {
+                task_name: task.name.clone(),
+                path: task.path.clone(),
+                branch: task.branch.clone(),
+                strategy: task.strategy,
+            });
+        }
+    }
+    for (task_name, record) in &state.tasks {
+        if !tasks.iter().any(|task| &task.name == task_name) {
+            findings.push(Finding::Missing {
+                task_name: task_name.clone(),
+                record: record.clone(),
+            });
+        }
+    }
+    Ok(findings)
+}
+
+/// 不整合を解消する
+/// 記録の無いワークスペースは現在の状態から記録を作り、ディレクトリの無い記録は削除する
+pub fn fix(manager: &RepoManager, findings: &[Finding]) -> Result<()> {
+    let mut state = manager.load_state()?;
+    let mut prune_worktrees = false;
+    for finding in findings {
+        match finding {
+            Finding::Orphan {
+                task_name,
+                path,
+                branch,
+                strategy,
+            } => {
+                let base_branch = manager.default_branch().unwrap_or_else(|_| "-".to_string());
+                // 作成日時は分からないのでディレクトリの更新日時で代用する
+                let created_at = fs::metadata(path)
+                    .and_then(|meta| meta.modified())
+                    .map(state::timestamp)
+                    .unwrap_or_else(|_| state::now_timestamp());
+                state.tasks.insert(
+                    task_name.clone(),
+                    TaskRecord {
+                        branch: branch.clone().unwrap_or_else(|| task_name.clone()),
+                        base_branch,
+                        strategy: *strategy,
+                        created_at,
+                        issue: None,
+                        tool: None,
+                        base_commit: None,
+                    },
+                );
+                println!("記録を追加しました: {}", task_name);
+            }
+            Finding::Missing { task_name, record } => {
+                state.tasks.remove(task_name);
+                prune_worktrees |= record.strategy == WorkspaceStrategy::Worktree;
+                println!("記録を削除しました: {}", task_name);
+            }
+        }
+    }
+    manager.save_state(&state)?;
+
+    let base_dir = manager.base_dir();
+    if prune_worktrees && base_dir.exists() {
+        let args = vec![
+            "-C".to_string(),
+            base_dir.display().to_string(),
+            "worktree".to_string(),
+            "prune".to_string(),
+        ];
+        run_command("git", &args, None)?;
+    }
+    Ok(())
+}
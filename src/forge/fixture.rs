@@ -0,0 +1,184 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Forge, PullRequest};
+use crate::issue::{Issue, IssueQuery};
+
+/// JSON / TOML のフィクスチャファイルを Issue と PR の取得先にする forge (テスト用)
+///
+/// Issue は `issues`、PR は `pulls` から読む。
+/// 拡張子が `.json` なら JSON、それ以外は TOML として扱う。
+pub struct Fixture {
+    path: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FixtureData {
+    #[serde(default)]
+    issues: Vec<Issue>,
+    #[serde(default)]
+    pulls: Vec<FixturePull>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FixturePull {
+    number: u32,
+    head: String,
+    base: String,
+    title: String,
+    #[serde(default)]
+    body: String,
+    state: String,
+    #[serde(default)]
+    url: String,
+}
+
+impl From<&FixturePull> for PullRequest {
+    fn from(pull: &FixturePull) -> PullRequest {
+        PullRequest {
+            number: pull.number,
+            state: pull.state.to_ascii_uppercase(),
+            url: pull.url.clone(),
+        }
+    }
+}
+
+impl Fixture {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn is_json(&self) -> bool {
+        self.path.extension().and_then(|ext| ext.to_str()) == Some("json")
+    }
+
+    fn load(&self) -> Result<FixtureData> {
+        let raw = fs::read_to_string(&self.path).with_context(|| {
+            format!(
+                "フィクスチャの読み込みに失敗しました: {}",
+                self.path.display()
+            )
+        })?;
+        let data = if self.is_json() {
+            serde_json::from_str(&raw).map_err(anyhow::Error::from)
+        } else {
+            toml::from_str(&raw).map_err(anyhow::Error::from)
+        };
+        data.with_context(|| {
+            format!(
+                "フィクスチャのパースに失敗しました: {}",
+                self.path.display()
+            )
+        })
+    }
+
+    fn not_found(&self, number: u32) -> anyhow::Error {
+        anyhow!(
+            "Issue #{} が見つかりません (フィクスチャ: {})",
+            number,
+            self.path.display()
+        )
+    }
+}
+
+impl Forge for Fixture {
+    fn check_available(&self) -> Result<()> {
+        if !self.path.exists() {
+            bail!("フィクスチャが見つかりません: {}", self.path.display());
+        }
+        Ok(())
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    fn list_issues(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        Ok(self
+            .load()?
+            .issues
+            .into_iter()
+            .filter(|issue| query.matches(issue))
+            .take(query.limit)
+            .collect())
+    }
+
+    fn view_issue(&self, number: u32) -> Result<Issue> {
+        self.load()?
+            .issues
+            .into_iter()
+            .find(|issue| issue.number == number)
+            .ok_or_else(|| self.not_found(number))
+    }
+
+    fn find_pull_request(&self, branch: &str) -> Result<Option<PullRequest>> {
+        Ok(self
+            .load()?
+            .pulls
+            .iter()
+            .rev()
+            .find(|pull| pull.head == branch)
+            .map(PullRequest::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_fixture(path: &std::path::Path, data: &FixtureData) {
+        let content = if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+            serde_json::to_string_pretty(data).unwrap()
+        } else {
+            toml::to_string_pretty(data).unwrap()
+        };
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_fixture_roundtrip() {
+        for name in ["forge.toml", "forge.json"] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join(name);
+            let data = FixtureData {
+                issues: vec![Issue {
+                    number: 1,
+                    title: "Fix login".to_string(),
+                    body: "Steps".to_string(),
+                    state: "OPEN".to_string(),
+                    url: String::new(),
+                    labels: vec!["bug".to_string()],
+                    assignees: Vec::new(),
+                    milestone: None,
+                    comments: Vec::new(),
+                }],
+                pulls: vec![FixturePull {
+                    number: 1,
+                    head: "aicloner-issue1".to_string(),
+                    base: "main".to_string(),
+                    title: "Fix login".to_string(),
+                    body: "Closes #1".to_string(),
+                    state: "merged".to_string(),
+                    url: "fixture://pulls/1".to_string(),
+                }],
+            };
+            write_fixture(&path, &data);
+            let forge = Fixture::new(path);
+
+            assert_eq!(forge.list_issues(&IssueQuery::default()).unwrap().len(), 1);
+            assert_eq!(forge.view_issue(1).unwrap().title, "Fix login");
+            assert!(forge.view_issue(2).is_err());
+
+            let found = forge.find_pull_request("aicloner-issue1").unwrap().unwrap();
+            assert_eq!(
+                (found.number, found.state.as_str()),
+                (1, "MERGED"),
+                "{}",
+                name
+            );
+            assert_eq!(forge.find_pull_request("other").unwrap(), None);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+
+use super::{check_cli, run_cli, run_cli_quiet, Forge, PullRequest};
+use crate::issue::{Issue, IssueComment, IssueQuery, IssueState};
+
+/// Gitea / Forgejo の CLI (`tea`) を使う forge
+///
+/// tea の JSON 出力はバージョンによってキー名や値の型が異なるため、
+/// `serde_json::Value` から寛容に読み取る。
+pub struct Gitea {
+    dir: PathBuf,
+}
+
+impl Gitea {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl Forge for Gitea {
+    fn check_available(&self) -> Result<()> {
+        check_cli("tea", "Gitea CLI (tea)", "https://gitea.com/gitea/tea")
+    }
+
+    fn list_issues(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        let state = match query.state {
+            IssueState::Open => "open",
+            IssueState::Closed => "closed",
+            IssueState::All => "all",
+        };
+        let limit = query.limit.to_string();
+        let labels = query.labels.join(",");
+        let mut args = vec![
+            "issues",
+            "list",
+            "--state",
+            state,
+            "--limit",
+            &limit,
+            "--output",
+            "json",
+            "--fields",
+            "index,title,state,url,labels,assignees,milestone",
+        ];
+        if !labels.is_empty() {
+            args.extend(["--labels", labels.as_str()]);
+        }
+        for (flag, value) in [
+            ("--assignee", &query.assignee),
+            ("--milestones", &query.milestone),
+            ("--keyword", &query.search),
+        ] {
+            if let Some(value) = value {
+                args.extend([flag, value.as_str()]);
+            }
+        }
+        let output = run_cli("tea", &self.dir, &args).context("Issue一覧の取得に失敗しました")?;
+        let values: Vec<Value> = serde_json::from_str(&output)
+            .context("tea issues list の出力を解析できませんでした")?;
+        values.iter().map(parse_issue).collect()
+    }
+
+    fn view_issue(&self, number: u32) -> Result<Issue> {
+        let index = number.to_string();
+        let output = run_cli(
+            "tea",
+            &self.dir,
+            &["issues", &index, "--comments", "--output", "json"],
+        )
+        .with_context(|| format!("Issue #{} が見つかりません", number))?;
+        let value: Value =
+            serde_json::from_str(&output).context("tea issues の出力を解析できませんでした")?;
+        parse_issue(&value)
+    }
+
+    fn find_pull_request(&self, branch: &str) -> Result<Option<PullRequest>> {
+        let output = run_cli_quiet(
+            "tea",
+            &self.dir,
+            &[
+                "pulls",
+                "list",
+                "--state",
+                "all",
+                "--output",
+                "json",
+                "--fields",
+                "index,state,head,url",
+            ],
+        )?;
+        let values: Vec<Value> =
+            serde_json::from_str(&output).context("tea pulls list の出力を解析できませんでした")?;
+        let Some(pr) = values
+            .iter()
+            .find(|value| string_field(value, &["head"]).as_deref() == Some(branch))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(PullRequest {
+            number: number_field(pr)?,
+            state: normalize_state(&string_field(pr, &["state"]).unwrap_or_default()),
+            url: string_field(pr, &["url", "html_url"]).unwrap_or_default(),
+        }))
+    }
+}
+
+fn parse_issue(value: &Value) -> Result<Issue> {
+    Ok(Issue {
+        number: number_field(value)?,
+        title: string_field(value, &["title"]).unwrap_or_default(),
+        body: string_field(value, &["body"]).unwrap_or_default(),
+        state: normalize_state(&string_field(value, &["state"]).unwrap_or_default()),
+        url: string_field(value, &["url", "html_url"]).unwrap_or_default(),
+        labels: names(value.get("labels"), &["name"]),
+        assignees: names(value.get("assignees"), &["login", "username"]),
+        milestone: value.get("milestone").and_then(|milestone| {
+            milestone
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| string_field(milestone, &["title"]))
+                .filter(|title| !title.is_empty())
+        }),
+        comments: value
+            .get("comments")
+            .and_then(Value::as_array)
+            .map(|comments| comments.iter().map(parse_comment).collect())
+            .unwrap_or_default(),
+    })
+}
+
+fn parse_comment(value: &Value) -> IssueComment {
+    let author = ["user", "author", "poster"]
+        .iter()
+        .filter_map(|key| value.get(*key))
+        .find_map(|user| {
+            user.as_str()
+                .map(str::to_string)
+                .or_else(|| string_field(user, &["login", "username"]))
+        })
+        .unwrap_or_else(|| "ghost".to_string());
+    IssueComment {
+        author,
+        body: string_field(value, &["body"]).unwrap_or_default(),
+        created_at: string_field(value, &["created", "created_at"]).unwrap_or_default(),
+    }
+}
+
+/// Issue / PR の番号 (`index` は文字列で返ることがある)
+fn number_field(value: &Value) -> Result<u32> {
+    for key in ["index", "number"] {
+        match value.get(key) {
+            Some(Value::Number(number)) => {
+                if let Some(number) = number.as_u64().and_then(|n| u32::try_from(n).ok()) {
+                    return Ok(number);
+                }
+            }
+            Some(Value::String(text)) => {
+                if let Ok(number) = text.trim().trim_start_matches('#').parse() {
+                    return Ok(number);
+                }
+            }
+            _ => {}
+        }
+    }
+    bail!("tea の出力に番号がありません: {}", value)
+}
+
+fn string_field(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| value.get(*key))
+        .find_map(|field| field.as_str().map(str::to_string))
+}
+
+/// 名前の配列、オブジェクトの配列、区切り文字列のいずれかから名前を取り出す
+fn names(value: Option<&Value>, keys: &[&str]) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| {
+                item.as_str()
+                    .map(str::to_string)
+                    .or_else(|| string_field(item, keys))
+            })
+            .collect(),
+        Some(Value::String(text)) => text
+            .split([',', ' '])
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Gitea の状態 (`open` / `closed` / `merged`) を gh と同じ表記にそろえる
+fn normalize_state(state: &str) -> String {
+    match state.to_ascii_lowercase().as_str() {
+        "open" => "OPEN",
+        "merged" => "MERGED",
+        _ => "CLOSED",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tea_issue() {
+        let listed: Value = serde_json::from_str(
+            r#"{"index":"5","title":"Fix login","state":"open",
+                "url":"https://codeberg.org/o/r/issues/5","labels":"bug ui"}"#,
+        )
+        .unwrap();
+        let issue = parse_issue(&listed).unwrap();
+        assert_eq!(issue.number, 5);
+        assert_eq!(issue.state, "OPEN");
+        assert_eq!(issue.labels, vec!["bug".to_string(), "ui".to_string()]);
+
+        let viewed: Value = serde_json::from_str(
+            r#"{"number":5,"title":"Fix login","body":"Steps","state":"closed",
+                "html_url":"https://codeberg.org/o/r/issues/5",
+                "labels":[{"name":"bug"}],"assignees":[{"login":"alice"}],
+                "comments":[{"user":{"login":"bob"},"body":"+1","created":"2024-05-01T10:00:00Z"}]}"#,
+        )
+        .unwrap();
+        let issue = parse_issue(&viewed).unwrap();
+        assert_eq!(issue.state, "CLOSED");
+        assert_eq!(issue.url, "https://codeberg.org/o/r/issues/5");
+        assert_eq!(issue.assignees, vec!["alice".to_string()]);
+        assert_eq!(issue.comments[0].author, "bob");
+        assert_eq!(issue.comments[0].created_at, "2024-05-01T10:00:00Z");
+    }
+}
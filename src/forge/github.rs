@@ -0,0 +1,90 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use super::{check_cli, run_cli, run_cli_quiet, Forge, PullRequest};
+use crate::issue::{self, Issue, IssueQuery, IssueState};
+
+/// GitHub CLI (`gh`) を使う forge
+pub struct GitHub {
+    /// `gh` がリポジトリを判別するための作業ディレクトリ (base)
+    dir: PathBuf,
+}
+
+impl GitHub {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl Forge for GitHub {
+    fn check_available(&self) -> Result<()> {
+        check_cli("gh", "GitHub CLI (gh)", "https://cli.github.com/")
+    }
+
+    fn list_issues(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        let state = match query.state {
+            IssueState::Open => "open",
+            IssueState::Closed => "closed",
+            IssueState::All => "all",
+        };
+        let limit = query.limit.to_string();
+        let mut args = vec![
+            "issue",
+            "list",
+            "--state",
+            state,
+            "--limit",
+            &limit,
+            "--json",
+            issue::LIST_FIELDS,
+        ];
+        for label in &query.labels {
+            args.extend(["--label", label.as_str()]);
+        }
+        for (flag, value) in [
+            ("--assignee", &query.assignee),
+            ("--milestone", &query.milestone),
+            ("--search", &query.search),
+        ] {
+            if let Some(value) = value {
+                args.extend([flag, value.as_str()]);
+            }
+        }
+        let output = run_cli("gh", &self.dir, &args).context("Issue一覧の取得に失敗しました")?;
+        serde_json::from_str(&output).context("gh issue list の出力を解析できませんでした")
+    }
+
+    fn view_issue(&self, number: u32) -> Result<Issue> {
+        let number = number.to_string();
+        let output = run_cli(
+            "gh",
+            &self.dir,
+            &["issue", "view", &number, "--json", issue::VIEW_FIELDS],
+        )
+        .with_context(|| format!("Issue #{} が見つかりません", number))?;
+        serde_json::from_str(&output).context("gh issue view の出力を解析できませんでした")
+    }
+
+    fn find_pull_request(&self, branch: &str) -> Result<Option<PullRequest>> {
+        let output = run_cli_quiet(
+            "gh",
+            &self.dir,
+            &[
+                "pr",
+                "list",
+                "--head",
+                branch,
+                "--state",
+                "all",
+                "--json",
+                "number,state,url",
+                "--limit",
+                "1",
+            ],
+        )?;
+        let prs: Vec<PullRequest> =
+            serde_json::from_str(&output).context("gh pr list の出力を解析できませんでした")?;
+        Ok(prs.into_iter().next())
+    }
+}
@@ -0,0 +1,260 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{check_cli, run_cli, run_cli_quiet, Forge, PullRequest};
+use crate::issue::{Issue, IssueComment, IssueQuery, IssueState};
+
+/// GitLab CLI (`glab`) を使う forge
+///
+/// Issue と MR の取得は出力形式が安定している `glab api` で行う
+/// (`:id` は glab が作業ディレクトリのリポジトリに置き換える)。
+pub struct GitLab {
+    dir: PathBuf,
+}
+
+impl GitLab {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+#[derive(Deserialize)]
+struct GlUser {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct GlIssue {
+    iid: u32,
+    title: String,
+    #[serde(default)]
+    description: Option<String>,
+    state: String,
+    #[serde(default)]
+    web_url: String,
+    #[serde(default)]
+    labels: Vec<String>,
+    #[serde(default)]
+    assignees: Vec<GlUser>,
+    #[serde(default)]
+    milestone: Option<GlMilestone>,
+}
+
+#[derive(Deserialize)]
+struct GlMilestone {
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct GlNote {
+    author: Option<GlUser>,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    created_at: String,
+    /// ラベル変更などの自動生成ノート
+    #[serde(default)]
+    system: bool,
+}
+
+#[derive(Deserialize)]
+struct GlMergeRequest {
+    iid: u32,
+    state: String,
+    web_url: String,
+}
+
+impl From<GlIssue> for Issue {
+    fn from(issue: GlIssue) -> Issue {
+        Issue {
+            number: issue.iid,
+            title: issue.title,
+            body: issue.description.unwrap_or_default(),
+            state: normalize_state(&issue.state),
+            url: issue.web_url,
+            labels: issue.labels,
+            assignees: issue
+                .assignees
+                .into_iter()
+                .map(|user| user.username)
+                .collect(),
+            milestone: issue.milestone.map(|milestone| milestone.title),
+            comments: Vec::new(),
+        }
+    }
+}
+
+/// 絞り込み条件を GitLab の Issues API のクエリにする
+fn issues_endpoint(query: &IssueQuery) -> String {
+    let state = match query.state {
+        IssueState::Open => "opened",
+        IssueState::Closed => "closed",
+        IssueState::All => "all",
+    };
+    let mut params = vec![
+        ("state", state.to_string()),
+        ("per_page", query.limit.clamp(1, 100).to_string()),
+    ];
+    if !query.labels.is_empty() {
+        params.push(("labels", query.labels.join(",")));
+    }
+    match query.assignee.as_deref() {
+        Some("@me") => params.push(("scope", "assigned_to_me".to_string())),
+        Some(assignee) => params.push(("assignee_username", assignee.to_string())),
+        None => {}
+    }
+    if let Some(milestone) = &query.milestone {
+        params.push(("milestone", milestone.clone()));
+    }
+    if let Some(search) = &query.search {
+        params.push(("search", search.clone()));
+    }
+    let query: Vec<String> = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, encode_query(value)))
+        .collect();
+    format!("projects/:id/issues?{}", query.join("&"))
+}
+
+/// クエリ文字列の値をパーセントエンコードする
+fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// GitLab の状態 (`opened` / `closed` / `merged` / `locked`) を gh と同じ表記にそろえる
+fn normalize_state(state: &str) -> String {
+    match state {
+        "opened" => "OPEN",
+        "merged" => "MERGED",
+        _ => "CLOSED",
+    }
+    .to_string()
+}
+
+impl Forge for GitLab {
+    fn check_available(&self) -> Result<()> {
+        check_cli(
+            "glab",
+            "GitLab CLI (glab)",
+            "https://gitlab.com/gitlab-org/cli",
+        )
+    }
+
+    fn list_issues(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        let output = run_cli("glab", &self.dir, &["api", &issues_endpoint(query)])
+            .context("Issue一覧の取得に失敗しました")?;
+        let issues: Vec<GlIssue> =
+            serde_json::from_str(&output).context("glab api の出力を解析できませんでした")?;
+        Ok(issues
+            .into_iter()
+            .take(query.limit)
+            .map(Issue::from)
+            .collect())
+    }
+
+    fn view_issue(&self, number: u32) -> Result<Issue> {
+        let output = run_cli(
+            "glab",
+            &self.dir,
+            &["api", &format!("projects/:id/issues/{}", number)],
+        )
+        .with_context(|| format!("Issue #{} が見つかりません", number))?;
+        let issue: GlIssue =
+            serde_json::from_str(&output).context("glab api の出力を解析できませんでした")?;
+
+        let notes = run_cli(
+            "glab",
+            &self.dir,
+            &[
+                "api",
+                &format!(
+                    "projects/:id/issues/{}/notes?sort=asc&order_by=created_at&per_page=100",
+                    number
+                ),
+            ],
+        )?;
+        let notes: Vec<GlNote> =
+            serde_json::from_str(&notes).context("glab api の出力を解析できませんでした")?;
+
+        let mut issue = Issue::from(issue);
+        issue.comments = notes
+            .into_iter()
+            .filter(|note| !note.system)
+            .map(|note| IssueComment {
+                author: note
+                    .author
+                    .map(|user| user.username)
+                    .unwrap_or_else(|| "ghost".to_string()),
+                body: note.body,
+                created_at: note.created_at,
+            })
+            .collect();
+        Ok(issue)
+    }
+
+    fn find_pull_request(&self, branch: &str) -> Result<Option<PullRequest>> {
+        let output = run_cli_quiet(
+            "glab",
+            &self.dir,
+            &[
+                "api",
+                &format!(
+                    "projects/:id/merge_requests?source_branch={}&state=all&per_page=1",
+                    encode_query(branch)
+                ),
+            ],
+        )?;
+        let mrs: Vec<GlMergeRequest> =
+            serde_json::from_str(&output).context("glab api の出力を解析できませんでした")?;
+        Ok(mrs.into_iter().next().map(|mr| PullRequest {
+            number: mr.iid,
+            state: normalize_state(&mr.state),
+            url: mr.web_url,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_gitlab_issue() {
+        let json = r#"{"iid":4,"title":"Fix login","description":null,"state":"opened",
+            "web_url":"https://gitlab.com/g/r/-/issues/4","labels":["bug"],
+            "assignees":[{"id":1,"username":"alice","name":"Alice"}]}"#;
+        let issue = Issue::from(serde_json::from_str::<GlIssue>(json).unwrap());
+        assert_eq!(issue.number, 4);
+        assert_eq!(issue.state, "OPEN");
+        assert_eq!(issue.body, "");
+        assert_eq!(issue.labels, vec!["bug".to_string()]);
+        assert_eq!(issue.assignees, vec!["alice".to_string()]);
+        assert_eq!(normalize_state("merged"), "MERGED");
+        assert_eq!(normalize_state("locked"), "CLOSED");
+    }
+
+    #[test]
+    fn test_issues_endpoint() {
+        let query = IssueQuery {
+            labels: vec!["bug".to_string(), "good first issue".to_string()],
+            assignee: Some("@me".to_string()),
+            search: Some("log in".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            issues_endpoint(&query),
+            "projects/:id/issues?state=opened&per_page=30&labels=bug%2Cgood%20first%20issue\
+             &scope=assigned_to_me&search=log%20in"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use super::{Forge, PullRequest};
+use crate::issue::{Issue, IssueComment, IssueQuery};
+
+/// PR の代わりに状態を書いておくファイル名
+const PULLS_FILE: &str = "pulls.toml";
+/// コメントの区切り行の接頭辞
+const COMMENT_MARKER: &str = "<!-- aicloner:comment";
+
+/// `.aicloner/issues/` に置いた Markdown ファイルを Issue として扱う forge
+///
+/// - ファイル名は `<番号>.md` または `<番号>-<任意>.md`
+/// - 先頭に `+++` で囲んだ TOML (`title` / `state` / `labels` / `assignees` / `milestone`) を置ける
+/// - `title` が無ければ最初の `# ` 見出しをタイトルにする
+/// - コメントは `<!-- aicloner:comment author="..." created_at="..." -->` の行で区切って書く
+/// - PR は `pulls.toml` の `[[pulls]]` (`number` / `head` / `state`) から読む
+pub struct LocalIssues {
+    dir: PathBuf,
+}
+
+impl LocalIssues {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// 番号順の Issue ファイル
+    fn issue_files(&self) -> Result<Vec<(u32, PathBuf)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let entries = fs::read_dir(&self.dir).with_context(|| {
+            format!("Issue ディレクトリを読み込めません: {}", self.dir.display())
+        })?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+                continue;
+            }
+            if let Some(number) = file_number(&path) {
+                files.push((number, path));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn issue_file(&self, number: u32) -> Result<PathBuf> {
+        self.issue_files()?
+            .into_iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, path)| path)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Issue #{} が見つかりません: {} に {}.md がありません",
+                    number,
+                    self.dir.display(),
+                    number
+                )
+            })
+    }
+
+    fn load_pulls(&self) -> Result<PullsFile> {
+        let path = self.dir.join(PULLS_FILE);
+        if !path.exists() {
+            return Ok(PullsFile::default());
+        }
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("{} の読み込みに失敗しました", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("{} のパースに失敗しました", path.display()))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FrontMatter {
+    title: Option<String>,
+    state: Option<String>,
+    labels: Vec<String>,
+    assignees: Vec<String>,
+    milestone: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PullsFile {
+    #[serde(default)]
+    pulls: Vec<LocalPull>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct LocalPull {
+    number: u32,
+    head: String,
+    /// `OPEN` / `CLOSED` / `MERGED` (手で書き換えて状態を変える)
+    state: String,
+}
+
+impl Forge for LocalIssues {
+    fn check_available(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    fn list_issues(&self, query: &IssueQuery) -> Result<Vec<Issue>> {
+        let mut issues = Vec::new();
+        for (number, path) in self.issue_files()? {
+            let issue = read_issue(number, &path)?;
+            if query.matches(&issue) {
+                issues.push(Issue {
+                    body: String::new(),
+                    comments: Vec::new(),
+                    ..issue
+                });
+            }
+        }
+        issues.truncate(query.limit);
+        Ok(issues)
+    }
+
+    fn view_issue(&self, number: u32) -> Result<Issue> {
+        read_issue(number, &self.issue_file(number)?)
+    }
+
+    fn find_pull_request(&self, branch: &str) -> Result<Option<PullRequest>> {
+        let path = self.dir.join(PULLS_FILE);
+        Ok(self
+            .load_pulls()?
+            .pulls
+            .into_iter()
+            .rev()
+            .find(|pull| pull.head == branch)
+            .map(|pull| PullRequest {
+                number: pull.number,
+                state: pull.state.to_ascii_uppercase(),
+                url: path.display().to_string(),
+            }))
+    }
+}
+
+/// `12.md` / `12-fix-login.md` の先頭の番号
+fn file_number(path: &Path) -> Option<u32> {
+    let stem = path.file_stem()?.to_str()?;
+    let digits: String = stem.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let rest = &stem[digits.len()..];
+    if !(rest.is_empty() || rest.starts_with('-')) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn read_issue(number: u32, path: &Path) -> Result<Issue> {
+    let raw = fs::read_to_string(path)
+        .with_context(|| format!("{} の読み込みに失敗しました", path.display()))?;
+    let mut issue = parse_issue(number, &raw)
+        .with_context(|| format!("Issue ファイルを解析できません: {}", path.display()))?;
+    issue.url = path.display().to_string();
+    Ok(issue)
+}
+
+fn parse_issue(number: u32, raw: &str) -> Result<Issue> {
+    let raw = raw.replace("\r\n", "\n");
+    let (front, content) = match raw.strip_prefix("+++\n") {
+        Some(rest) => {
+            let Some((front, content)) = rest.split_once("\n+++") else {
+                bail!("先頭の +++ が閉じられていません");
+            };
+            let front: FrontMatter = toml::from_str(front).context("+++ 内の TOML が不正です")?;
+            (front, content.strip_prefix('\n').unwrap_or(content))
+        }
+        None => (FrontMatter::default(), raw.as_str()),
+    };
+
+    let (mut body, comments) = split_comments(content);
+    let title = match front.title {
+        Some(title) => title,
+        None => {
+            let heading = body
+                .lines()
+                .find(|line| !line.trim().is_empty())
+                .and_then(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string());
+            match heading {
+                Some(title) => {
+                    body = body
+                        .trim_start()
+                        .split_once('\n')
+                        .map(|(_, rest)| rest.to_string())
+                        .unwrap_or_default();
+                    title
+                }
+                None => format!("Issue #{}", number),
+            }
+        }
+    };
+
+    Ok(Issue {
+        number,
+        title,
+        body: body.trim().to_string(),
+        state: front
+            .state
+            .map(|state| state.to_ascii_uppercase())
+            .unwrap_or_else(|| "OPEN".to_string()),
+        url: String::new(),
+        labels: front.labels,
+        assignees: front.assignees,
+        milestone: front.milestone,
+        comments,
+    })
+}
+
+/// 本文と、区切り行で追記されたコメントに分ける
+fn split_comments(content: &str) -> (String, Vec<IssueComment>) {
+    let mut body = String::new();
+    let mut comments: Vec<IssueComment> = Vec::new();
+    for line in content.lines() {
+        if let Some(attrs) = line.trim().strip_prefix(COMMENT_MARKER) {
+            comments.push(IssueComment {
+                author: attribute(attrs, "author").unwrap_or_else(|| "ghost".to_string()),
+                body: String::new(),
+                created_at: attribute(attrs, "created_at").unwrap_or_default(),
+            });
+            continue;
+        }
+        let target = match comments.last_mut() {
+            Some(comment) => &mut comment.body,
+            None => &mut body,
+        };
+        target.push_str(line);
+        target.push('\n');
+    }
+    for comment in &mut comments {
+        comment.body = comment.body.trim().to_string();
+    }
+    (body, comments)
+}
+
+/// `key="value"` の値
+fn attribute(attrs: &str, key: &str) -> Option<String> {
+    let start = attrs.find(&format!("{}=\"", key))? + key.len() + 2;
+    let end = attrs[start..].find('"')? + start;
+    Some(attrs[start..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_local_issue() {
+        let raw = "+++\nlabels = [\"bug\"]\nassignees = [\"alice\"]\n+++\n\
+                   # Fix login\n\nSteps to reproduce\n\n\
+                   <!-- aicloner:comment author=\"bob\" created_at=\"2024-05-01T10:00:00Z\" -->\n\
+                   Repro on Safari\n";
+        let issue = parse_issue(3, raw).unwrap();
+        assert_eq!(issue.title, "Fix login");
+        assert_eq!(issue.body, "Steps to reproduce");
+        assert_eq!(issue.state, "OPEN");
+        assert_eq!(issue.labels, vec!["bug".to_string()]);
+        assert_eq!(issue.assignees, vec!["alice".to_string()]);
+        assert_eq!(issue.comments.len(), 1);
+        assert_eq!(issue.comments[0].author, "bob");
+        assert_eq!(issue.comments[0].body, "Repro on Safari");
+
+        let closed = parse_issue(4, "+++\ntitle = \"Old\"\nstate = \"closed\"\n+++\nbody").unwrap();
+        assert_eq!(closed.title, "Old");
+        assert_eq!(closed.state, "CLOSED");
+        assert_eq!(closed.body, "body");
+    }
+
+    #[test]
+    fn test_local_issues_and_pulls() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("1-first.md"), "# First\n").unwrap();
+        fs::write(
+            dir.path().join("2.md"),
+            "+++\nstate = \"closed\"\n+++\n# Second\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.md"), "# Not an issue\n").unwrap();
+        let forge = LocalIssues::new(dir.path().to_path_buf());
+
+        let open: Vec<u32> = forge
+            .list_issues(&IssueQuery::default())
+            .unwrap()
+            .iter()
+            .map(|i| i.number)
+            .collect();
+        assert_eq!(open, vec![1]);
+        assert!(forge.view_issue(3).is_err());
+
+        assert_eq!(forge.find_pull_request("feature").unwrap(), None);
+        fs::write(
+            dir.path().join(PULLS_FILE),
+            "[[pulls]]\nnumber = 1\nhead = \"feature\"\nbase = \"main\"\nstate = \"merged\"\n",
+        )
+        .unwrap();
+        let found = forge.find_pull_request("feature").unwrap().unwrap();
+        assert_eq!((found.number, found.state.as_str()), (1, "MERGED"));
+    }
+}
@@ -0,0 +1,206 @@
+mod fixture;
+mod gitea;
+mod github;
+mod gitlab;
+mod local;
+
+use std::{
+    env, fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    issue::{Issue, IssueQuery},
+    repo::{run_command_capture, run_command_quiet, RepoManager},
+};
+
+pub use fixture::Fixture;
+pub use gitea::Gitea;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+pub use local::LocalIssues;
+
+/// フィクスチャファイルのパスを指定して、設定に関わらず fixture forge を使う環境変数
+pub const FORGE_FIXTURE_ENV: &str = "AICLONER_FORGE_FIXTURE";
+
+/// `forge = "fixture"` で `forge_fixture` を省略したときのフィクスチャ
+const DEFAULT_FIXTURE: &str = ".aicloner/forge-fixture.toml";
+
+/// 使用する forge の種類 (`aicloner.toml` の `forge`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForgeKind {
+    /// GitHub (`gh`)
+    Github,
+    /// GitLab (`glab`)
+    Gitlab,
+    /// Gitea / Forgejo (`tea`)
+    Gitea,
+    /// `.aicloner/issues/` に置いた Markdown ファイル
+    Local,
+    /// JSON / TOML のフィクスチャファイル (テスト用)
+    Fixture,
+}
+
+impl fmt::Display for ForgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ForgeKind::Github => "github",
+            ForgeKind::Gitlab => "gitlab",
+            ForgeKind::Gitea => "gitea",
+            ForgeKind::Local => "local",
+            ForgeKind::Fixture => "fixture",
+        };
+        f.pad(name)
+    }
+}
+
+/// PR / MR の概要 (状態は `OPEN` / `CLOSED` / `MERGED` にそろえる)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequest {
+    pub number: u32,
+    pub state: String,
+    pub url: String,
+}
+
+/// Issue の取得と PR/MR の検索
+///
+/// `status` が複数のスレッドから PR を問い合わせるため `Sync` を要求する。
+pub trait Forge: Send + Sync {
+    /// 必要な CLI が使えるかを確認する
+    fn check_available(&self) -> Result<()>;
+
+    /// ネットワーク越しに問い合わせるか (false ならオフラインモードでも使える)
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    /// 条件に合う Issue の一覧 (本文とコメントは含まない)
+    fn list_issues(&self, query: &IssueQuery) -> Result<Vec<Issue>>;
+
+    /// 本文・担当者・コメントを含めて Issue を取得する
+    fn view_issue(&self, number: u32) -> Result<Issue>;
+
+    /// `branch` から作られた PR / MR を探す (クローズ・マージ済みも含む)
+    fn find_pull_request(&self, branch: &str) -> Result<Option<PullRequest>>;
+}
+
+/// 設定と `repo_url` から forge を選ぶ (`AICLONER_FORGE_FIXTURE` があれば最優先)
+pub fn for_manager(manager: &RepoManager) -> Box<dyn Forge> {
+    if let Some(path) = env::var_os(FORGE_FIXTURE_ENV).filter(|path| !path.is_empty()) {
+        return Box::new(Fixture::new(PathBuf::from(path)));
+    }
+    let kind = manager
+        .config
+        .forge
+        .unwrap_or_else(|| detect(&manager.config.repo_url));
+    let base_dir = manager.base_dir();
+    match kind {
+        ForgeKind::Github => Box::new(GitHub::new(base_dir)),
+        ForgeKind::Gitlab => Box::new(GitLab::new(base_dir)),
+        ForgeKind::Gitea => Box::new(Gitea::new(base_dir)),
+        ForgeKind::Local => Box::new(LocalIssues::new(manager.state_dir().join("issues"))),
+        ForgeKind::Fixture => Box::new(Fixture::new(
+            manager.resolve_path(
+                manager
+                    .config
+                    .forge_fixture
+                    .as_deref()
+                    .unwrap_or(DEFAULT_FIXTURE),
+            ),
+        )),
+    }
+}
+
+/// `repo_url` のホスト名から forge を推測する
+/// ホスト名の無いローカルパスは local、判別できないホストは github とみなす
+pub fn detect(repo_url: &str) -> ForgeKind {
+    let Some(host) = url_host(repo_url) else {
+        return ForgeKind::Local;
+    };
+    let host = host.to_ascii_lowercase();
+    if host.contains("gitlab") {
+        ForgeKind::Gitlab
+    } else if host.contains("gitea") || host.contains("forgejo") || host == "codeberg.org" {
+        ForgeKind::Gitea
+    } else {
+        ForgeKind::Github
+    }
+}
+
+/// URL (`https://host/...`, `ssh://user@host:port/...`, `user@host:path`) のホスト名
+pub(crate) fn url_host(url: &str) -> Option<String> {
+    let url = url.trim();
+    if let Some((scheme, rest)) = url.split_once("://") {
+        if scheme.eq_ignore_ascii_case("file") {
+            return None;
+        }
+        let authority = rest.split('/').next().unwrap_or(rest);
+        let host = authority.rsplit('@').next().unwrap_or(authority);
+        let host = host.split(':').next().unwrap_or(host);
+        return (!host.is_empty()).then(|| host.to_string());
+    }
+    // scp 形式 (`git@host:owner/repo.git`)。`C:\...` のようなドライブ名は除く
+    let (authority, _) = url.split_once(':')?;
+    if authority.contains('/') || authority.contains('\\') || authority.len() <= 1 {
+        return None;
+    }
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+/// forge の CLI が実行できるかを確認する
+fn check_cli(program: &str, name: &str, install_url: &str) -> Result<()> {
+    match run_command_quiet(program, &["--version".to_string()], None) {
+        Ok(_) => Ok(()),
+        Err(_) => bail!(
+            "{} がインストールされていません。\n\
+             {} からインストールしてください。",
+            name,
+            install_url
+        ),
+    }
+}
+
+/// forge の CLI を実行する (コマンドは標準エラーにログを出す)
+fn run_cli(program: &str, dir: &Path, args: &[&str]) -> Result<String> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    run_command_capture(program, &args, Some(dir))
+}
+
+/// 並列に行う読み取り専用の問い合わせ用
+fn run_cli_quiet(program: &str, dir: &Path, args: &[&str]) -> Result<String> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    run_command_quiet(program, &args, Some(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_forge_from_repo_url() {
+        let cases = [
+            ("https://github.com/owner/repo.git", ForgeKind::Github),
+            ("git@github.com:owner/repo.git", ForgeKind::Github),
+            ("https://gitlab.com/group/sub/repo.git", ForgeKind::Gitlab),
+            (
+                "ssh://git@gitlab.example.com:2222/group/repo.git",
+                ForgeKind::Gitlab,
+            ),
+            ("https://codeberg.org/owner/repo.git", ForgeKind::Gitea),
+            ("git@gitea.example.com:owner/repo.git", ForgeKind::Gitea),
+            ("https://git.example.com/owner/repo.git", ForgeKind::Github),
+            ("/srv/git/repo.git", ForgeKind::Local),
+            ("file:///srv/git/repo.git", ForgeKind::Local),
+            ("../repo", ForgeKind::Local),
+            (r"C:\repos\repo", ForgeKind::Local),
+        ];
+        for (url, expected) in cases {
+            assert_eq!(detect(url), expected, "{}", url);
+        }
+    }
+}
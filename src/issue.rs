@@ -0,0 +1,259 @@
+use std::env;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// forge から取得する Issue (`aicloner issues` の出力単位、`start` のプロンプトに埋め込む内容)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Issue {
+    pub number: u32,
+    pub title: String,
+    /// 本文 (`issues` の一覧では取得しない)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub body: String,
+    /// `OPEN` / `CLOSED`
+    pub state: String,
+    #[serde(default)]
+    pub url: String,
+    /// ラベル名の一覧
+    #[serde(default, deserialize_with = "label_names")]
+    pub labels: Vec<String>,
+    /// 担当者のログイン名 (`issues` の一覧では取得しない)
+    #[serde(
+        default,
+        deserialize_with = "logins",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub assignees: Vec<String>,
+    /// マイルストーンのタイトル
+    #[serde(
+        default,
+        deserialize_with = "milestone_title",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub milestone: Option<String>,
+    /// 古い順のコメント (`issues` の一覧では取得しない)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<IssueComment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueComment {
+    /// 投稿者のログイン名
+    #[serde(default, deserialize_with = "login")]
+    pub author: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default, alias = "createdAt")]
+    pub created_at: String,
+}
+
+/// `start` で取得する gh の JSON フィールド
+pub const VIEW_FIELDS: &str = "number,title,body,state,url,labels,assignees,milestone,comments";
+
+/// `issues` の一覧で取得する gh の JSON フィールド
+pub const LIST_FIELDS: &str = "number,title,state,url,labels,assignees,milestone";
+
+/// 一覧に含める Issue の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum IssueState {
+    #[default]
+    Open,
+    Closed,
+    All,
+}
+
+/// `issues` の絞り込み条件
+#[derive(Debug, Clone)]
+pub struct IssueQuery {
+    pub state: IssueState,
+    /// すべてを持つ Issue に絞る
+    pub labels: Vec<String>,
+    /// 担当者のログイン名 (`@me` は自分)
+    pub assignee: Option<String>,
+    /// マイルストーンのタイトル
+    pub milestone: Option<String>,
+    /// 検索語 (forge の検索構文をそのまま渡す)
+    pub search: Option<String>,
+    pub limit: usize,
+}
+
+impl Default for IssueQuery {
+    fn default() -> Self {
+        Self {
+            state: IssueState::Open,
+            labels: Vec::new(),
+            assignee: None,
+            milestone: None,
+            search: None,
+            limit: 30,
+        }
+    }
+}
+
+impl IssueQuery {
+    /// 手元で絞り込む forge (local / fixture) 用の判定
+    /// `@me` は `USER` / `USERNAME` 環境変数のユーザーとみなし、検索語はタイトルと本文の部分一致で調べる
+    pub fn matches(&self, issue: &Issue) -> bool {
+        let state_matches = match self.state {
+            IssueState::Open => issue.state.eq_ignore_ascii_case("open"),
+            IssueState::Closed => !issue.state.eq_ignore_ascii_case("open"),
+            IssueState::All => true,
+        };
+        let assignee_matches = match self.assignee.as_deref() {
+            None => true,
+            Some("@me") => env::var("USER")
+                .or_else(|_| env::var("USERNAME"))
+                .is_ok_and(|me| issue.assignees.contains(&me)),
+            Some(assignee) => issue.assignees.iter().any(|login| login == assignee),
+        };
+        let search_matches = self.search.as_deref().is_none_or(|search| {
+            let search = search.to_lowercase();
+            issue.title.to_lowercase().contains(&search)
+                || issue.body.to_lowercase().contains(&search)
+        });
+        state_matches
+            && assignee_matches
+            && search_matches
+            && self.labels.iter().all(|label| issue.labels.contains(label))
+            && self
+                .milestone
+                .as_ref()
+                .is_none_or(|milestone| issue.milestone.as_ref() == Some(milestone))
+    }
+}
+
+/// gh の JSON ではラベルが `{"name": ...}` のオブジェクトで返るため名前だけを取り出す
+/// 名前の文字列で渡された場合もそのまま受け付ける
+fn label_names<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum LabelRepr {
+        Name(String),
+        Object { name: String },
+    }
+
+    let labels = Vec::<LabelRepr>::deserialize(deserializer)?;
+    Ok(labels
+        .into_iter()
+        .map(|label| match label {
+            LabelRepr::Name(name) | LabelRepr::Object { name } => name,
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum UserRepr {
+    Login(String),
+    Object { login: String },
+}
+
+impl From<UserRepr> for String {
+    fn from(user: UserRepr) -> String {
+        match user {
+            UserRepr::Login(login) | UserRepr::Object { login } => login,
+        }
+    }
+}
+
+/// gh の JSON ではユーザーが `{"login": ...}` のオブジェクトで返るためログイン名だけを取り出す
+fn logins<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let users = Vec::<UserRepr>::deserialize(deserializer)?;
+    Ok(users.into_iter().map(String::from).collect())
+}
+
+/// gh / GitLab の JSON ではマイルストーンが `{"title": ...}` のオブジェクトで返る
+fn milestone_title<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum MilestoneRepr {
+        Title(String),
+        Object { title: String },
+    }
+
+    let milestone = Option::<MilestoneRepr>::deserialize(deserializer)?;
+    Ok(milestone.map(|milestone| match milestone {
+        MilestoneRepr::Title(title) | MilestoneRepr::Object { title } => title,
+    }))
+}
+
+fn login<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    // 削除済みのユーザーは null になる
+    let user = Option::<UserRepr>::deserialize(deserializer)?;
+    Ok(user
+        .map(String::from)
+        .unwrap_or_else(|| "ghost".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_gh_issue_list() {
+        let json = r#"[{"number":3,"title":"Fix login","state":"OPEN",
+            "url":"https://github.com/o/r/issues/3",
+            "labels":[{"id":"x","name":"bug","color":"d73a4a","description":""}]}]"#;
+        let issues: Vec<Issue> = serde_json::from_str(json).unwrap();
+        assert_eq!(issues[0].number, 3);
+        assert_eq!(issues[0].labels, vec!["bug".to_string()]);
+
+        let roundtrip = serde_json::to_value(&issues[0]).unwrap();
+        assert_eq!(roundtrip["labels"], serde_json::json!(["bug"]));
+        assert!(roundtrip.get("comments").is_none());
+    }
+
+    #[test]
+    fn test_deserialize_gh_issue_view() {
+        let json = r#"{"number":3,"title":"Fix login","body":"Steps","state":"OPEN",
+            "url":"https://github.com/o/r/issues/3","labels":[],
+            "assignees":[{"id":"x","login":"alice","name":"Alice"}],
+            "comments":[{"author":{"login":"bob"},"authorAssociation":"MEMBER",
+                "body":"Repro on Safari","createdAt":"2024-05-01T10:00:00Z"},
+                {"author":null,"body":"+1","createdAt":"2024-05-02T10:00:00Z"}]}"#;
+        let issue: Issue = serde_json::from_str(json).unwrap();
+        assert_eq!(issue.body, "Steps");
+        assert_eq!(issue.assignees, vec!["alice".to_string()]);
+        assert_eq!(issue.comments[0].author, "bob");
+        assert_eq!(issue.comments[0].created_at, "2024-05-01T10:00:00Z");
+        assert_eq!(issue.comments[1].author, "ghost");
+    }
+
+    #[test]
+    fn test_issue_query_matches() {
+        let json = r#"{"number":3,"title":"Fix login","body":"Safari only","state":"OPEN",
+            "labels":["bug","ui"],"assignees":["alice"],"milestone":{"title":"v1.0"}}"#;
+        let issue: Issue = serde_json::from_str(json).unwrap();
+        assert_eq!(issue.milestone.as_deref(), Some("v1.0"));
+
+        let query = |f: fn(&mut IssueQuery)| {
+            let mut query = IssueQuery::default();
+            f(&mut query);
+            query.matches(&issue)
+        };
+        assert!(query(|_| {}));
+        assert!(!query(|q| q.state = IssueState::Closed));
+        assert!(query(
+            |q| q.labels = vec!["bug".to_string(), "ui".to_string()]
+        ));
+        assert!(!query(
+            |q| q.labels = vec!["bug".to_string(), "docs".to_string()]
+        ));
+        assert!(query(|q| q.assignee = Some("alice".to_string())));
+        assert!(!query(|q| q.milestone = Some("v2.0".to_string())));
+        assert!(query(|q| q.search = Some("SAFARI".to_string())));
+    }
+}
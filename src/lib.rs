@@ -1,5 +1,24 @@
 pub mod ai_tool;
+pub mod batch;
+pub mod branch;
 pub mod cli;
 pub mod config;
+pub mod config_edit;
+pub mod config_schema;
+pub mod doctor;
+pub mod forge;
+pub mod issue;
+pub mod output;
+pub mod prompt;
+pub mod prune;
 pub mod repo;
+#[cfg(unix)]
+pub mod recording;
+pub mod registry;
+pub mod safety;
+pub mod session_log;
 pub mod start;
+pub mod state;
+pub mod status;
+pub mod sync;
+pub mod workspace;
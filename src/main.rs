@@ -1,24 +1,64 @@
 mod ai_tool;
+mod batch;
+mod branch;
 mod cli;
 mod config;
+mod config_edit;
+mod config_schema;
+mod doctor;
+mod forge;
+mod issue;
+mod output;
+mod prompt;
+mod prune;
 mod repo;
+#[cfg(unix)]
+mod recording;
+mod registry;
+mod safety;
+mod session_log;
 mod start;
+mod state;
+mod status;
+mod sync;
+mod workspace;
 
+use std::collections::HashSet;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
+use serde::Serialize;
 
 use crate::{
-    cli::{Cli, Commands},
-    config::Config,
-    repo::RepoManager,
-    start::handle_start,
+    ai_tool::ToolRegistry,
+    batch::{run_headless, BatchOptions},
+    cli::{Cli, Commands, ConfigCommands, PromptCommands, ReposCommands},
+    config::{discover_config_path, Config, WorkspaceStrategy, CONFIG_FILE},
+    config_schema::CONFIG_VERSION,
+    issue::{Issue, IssueQuery},
+    output::{Collection, OutputFormat},
+    prompt::DEFAULT_TEMPLATE,
+    prune::PruneOptions,
+    registry::Registry,
+    repo::{confirm, detect_default_branch, RemoveOptions, RepoManager, TaskInfo},
+    session_log::{list_logs, show_log, LogEntry},
+    start::{
+        handle_resume, handle_start, issue_workspaces, render_start_prompt, resolve_task,
+        ResumeOptions, StartOptions,
+    },
+    state::format_age,
+    status::{collect_statuses, print_statuses, StatusOptions},
+    sync::{print_summary, sync_tasks, SyncOptions},
 };
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    let offline = cli.offline;
+    // 標準入力が端末でなければ、入力を待たずに非対話モードとして扱う
+    let non_interactive = cli.non_interactive;
+    let interactive = !non_interactive && io::stdin().is_terminal();
 
     match cli.command {
         Commands::Init(args) => {
@@ -31,58 +71,523 @@ fn main() -> Result<()> {
                 );
             }
 
+            if offline {
+                bail!("オフラインモードでは init を実行できません。");
+            }
+            let default_branch = match args.branch {
+                Some(branch) => branch,
+                None => {
+                    let branch = detect_default_branch(&args.repo_url)?;
+                    println!("✓ 既定のブランチ: {}", branch);
+                    branch
+                }
+            };
+
             let config_path = resolve_config_path(&repo_root, &args.config);
             let config = Config {
                 repo_url: args.repo_url,
                 base_dir: args.base_dir,
                 workspaces_dir: args.workspaces_dir,
+                workspace_strategy: args.strategy,
+                default_branch: Some(default_branch.clone()),
+                ..Config::default()
             };
             config.save(&config_path)?;
             let manager = RepoManager::new(config, config_path.clone());
-            manager.init_environment("main")?;
+            manager.init_environment(&default_branch)?;
             println!("初期化が完了しました: {}", repo_root.display());
+            // 一覧への登録に失敗しても初期化は済んでいるので、警告だけにする
+            let registered = Registry::load().and_then(|mut registry| {
+                let name = registry.add(&config_path, None)?;
+                registry.save()?;
+                Ok(name)
+            });
+            match registered {
+                Ok(name) => println!("✓ リポジトリ一覧に登録しました: {}", name),
+                Err(err) => eprintln!("警告: リポジトリ一覧への登録に失敗しました: {:#}", err),
+            }
         }
         Commands::Add(args) => {
-            let manager = load_manager(&args.config)?;
-            manager.create_task_clone(&args.task_name, &args.base_branch)?;
+            let mut manager = load_manager(args.config.as_deref(), offline)?;
+            if let Some(strategy) = args.strategy {
+                manager.config.workspace_strategy = strategy;
+            }
+            manager.config.dissociate |= args.dissociate;
+            let base_branch = match args.base_branch {
+                Some(branch) => branch,
+                None => manager.default_branch()?,
+            };
+            manager.create_task_clone(&args.task_name, &base_branch)?;
         }
         Commands::Rm(args) => {
-            let manager = load_manager(&args.config)?;
-            manager.remove_task_clone(&args.task_name, args.force)?;
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let options = RemoveOptions {
+                force: args.force,
+                archive: args.archive,
+                yes: args.force || non_interactive,
+            };
+            let task_name = manager.resolve_task_name(&args.task_name)?;
+            manager.remove_task_clone(&task_name, &options)?;
         }
         Commands::List(args) => {
-            let manager = load_manager(&args.config)?;
-            let tasks = manager.list_tasks()?;
-            println!("{:<12} {:<40} BRANCH", "TASK", "PATH");
-            for info in tasks {
-                let branch = info.branch.unwrap_or_else(|| "-".to_string());
-                println!("{:<12} {:<40} {}", info.name, info.path.display(), branch);
-            }
+            let targets = target_repos(args.all_repos, args.config.as_deref(), offline)?;
+            let (tasks, repos) =
+                collect_per_repo(&targets, args.all_repos, |manager| manager.list_tasks())?;
+            print_tasks(&tasks, args.all_repos.then_some(repos.as_slice()), args.format)?;
         }
         Commands::Start(args) => {
-            ensure_aicloner_repo(&args.config)?;
-            check_gh_installed()?;
 
-            let selected_tool = args.selected_tool();
-            selected_tool.check_installed()?;
+            let mut manager = load_manager(args.config.as_deref(), offline)?;
+            if let Some(strategy) = args.strategy {
+                manager.config.workspace_strategy = strategy;
+            }
+            manager.config.dissociate |= args.dissociate;
+            let tool = args.selected_tool().map(str::to_string);
+            let options = StartOptions {
+                plan: args.plan,
+                template: args.template,
+                record: args.record || manager.config.record_sessions,
+                base_branch: args.from,
+                branch: args.branch,
+                on_conflict: args.on_conflict,
+                interactive,
+            };
+            if !args.headless {
+                let [issue_number] = args.issue_numbers[..] else {
+                    bail!("複数の Issue を指定するには --headless を付けてください");
+                };
+                return start_issue(manager, issue_number, tool.as_deref(), &options);
+            }
+
+            let mut issue_numbers = args.issue_numbers;
+            if !args.labels.is_empty() {
+                let forge = forge::for_manager(&manager);
+                forge.check_available()?;
+                if forge.is_remote() {
+                    manager.ensure_online("Issue 一覧の取得")?;
+                }
+                let query = IssueQuery {
+                    labels: args.labels,
+                    limit: 100,
+                    ..Default::default()
+                };
+                issue_numbers.extend(forge.list_issues(&query)?.iter().map(|issue| issue.number));
+            }
+            let mut seen = HashSet::new();
+            issue_numbers.retain(|number| seen.insert(*number));
+            if issue_numbers.is_empty() {
+                bail!("開始する Issue がありません");
+            }
 
-            let manager = load_manager(&args.config)?;
-            handle_start(args.issue_number, selected_tool, manager, args.plan)?;
+            let selected_tool = ToolRegistry::load(&manager.config)?.resolve(tool.as_deref())?;
+            selected_tool.check_installed()?;
+            let batch = BatchOptions {
+                max_parallel: args.max_parallel,
+            };
+            run_headless(&manager, &issue_numbers, &selected_tool, &options, &batch)?;
+        }
+        Commands::Resume(args) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let target = args.target.unwrap_or_else(|| ".".to_string());
+            let task = resolve_task(&manager, &manager.resolve_task_name(&target)?)?;
+            let tool = args
+                .tool
+                .or_else(|| task.record.as_ref().and_then(|record| record.tool.clone()));
+            let selected_tool = ToolRegistry::load(&manager.config)?.resolve(tool.as_deref())?;
+            selected_tool.check_installed()?;
+            let options = ResumeOptions {
+                plan: args.plan,
+                template: args.template,
+                record: args.record || manager.config.record_sessions,
+                prompt: args.prompt,
+            };
+            handle_resume(&task.name, &selected_tool, &manager, &options)?;
         }
         Commands::Issues(args) => {
-            ensure_aicloner_repo(&args.config)?;
-            check_gh_installed()?;
-            let manager = load_manager(&args.config)?;
-            list_issues(&manager)?;
+            let mut targets = target_repos(args.all_repos, args.config.as_deref(), offline)?;
+            let query = args.query();
+            let (issues, repos) = collect_per_repo(&targets, args.all_repos, |manager| {
+                let forge = forge::for_manager(manager);
+                forge.check_available()?;
+                if forge.is_remote() {
+                    manager.ensure_online("Issue 一覧の取得")?;
+                }
+                let tasks = manager.list_tasks()?;
+                Ok(forge
+                    .list_issues(&query)?
+                    .into_iter()
+                    .map(|issue| {
+                        let workspaces = issue_workspaces(
+                            &tasks,
+                            issue.number,
+                            &manager.config.branch_template,
+                        );
+                        (issue, workspaces)
+                    })
+                    .collect())
+            })?;
+            let listings: Vec<IssueListing> = issues
+                .iter()
+                .map(|(issue, workspaces)| IssueListing {
+                    issue,
+                    workspaces: workspaces.clone(),
+                })
+                .collect();
+            if !args.pick {
+                let repos = args.all_repos.then_some(repos.as_slice());
+                return print_issues(&listings, repos, args.format);
+            }
+            // --pick は入力を求める指定なので、パイプからの番号も受け付ける
+            if non_interactive {
+                bail!("--pick は対話的な入力が必要です。番号を指定して start を実行してください。");
+            }
+            let Some(issue_number) = pick_issue(&listings)? else {
+                return Ok(());
+            };
+            let (_, manager) = targets.remove(0);
+            let options = StartOptions {
+                plan: args.plan,
+                template: DEFAULT_TEMPLATE.to_string(),
+                record: manager.config.record_sessions,
+                base_branch: None,
+                branch: None,
+                on_conflict: None,
+                interactive,
+            };
+            start_issue(manager, issue_number, args.tool.as_deref(), &options)?;
+        }
+        Commands::Fetch(args) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            manager.fetch_mirror()?;
+        }
+        Commands::Sync(args) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let task_names = if args.all {
+                manager
+                    .list_tasks()?
+                    .into_iter()
+                    .map(|info| info.name)
+                    .collect()
+            } else {
+                resolve_task_names(&manager, &args.task_names)?
+            };
+            let options = SyncOptions {
+                method: args.method.unwrap_or(manager.config.sync_method),
+                autostash: args.autostash,
+            };
+            let results = sync_tasks(&manager, &task_names, &options)?;
+            print_summary(&results);
+        }
+        Commands::Status(args) => {
+            let targets = target_repos(args.all_repos, args.config.as_deref(), offline)?;
+            let options = StatusOptions {
+                check_pull_requests: !args.no_pr,
+            };
+            let (statuses, repos) = collect_per_repo(&targets, args.all_repos, |manager| {
+                let tasks = if args.all_repos {
+                    manager.list_tasks()?
+                } else {
+                    status_tasks(manager, &args.task_names)?
+                };
+                Ok(collect_statuses(manager, &tasks, &options))
+            })?;
+            print_statuses(&statuses, args.all_repos.then_some(repos.as_slice()), args.format)?;
+        }
+        Commands::Prune(args) => {
+            let targets = target_repos(args.all_repos, args.config.as_deref(), offline)?;
+            let options = PruneOptions {
+                older_than: args.older_than,
+                check_forge: !args.no_pr,
+                fetch: !args.dry_run,
+            };
+            let (candidates, repos) = collect_per_repo(&targets, args.all_repos, |manager| {
+                if options.fetch && manager.mirror_dir().exists() && !manager.offline {
+                    manager.fetch_mirror()?;
+                }
+                prune::find_candidates(manager, &options)
+            })?;
+            if candidates.is_empty() {
+                println!("削除できるワークスペースはありません。");
+                return Ok(());
+            }
+            prune::print_candidates(&candidates, args.all_repos.then_some(repos.as_slice()));
+            if args.dry_run {
+                println!("--dry-run のため削除しません。");
+                return Ok(());
+            }
+            let prompt = format!("Remove {} workspaces? [y/N]: ", candidates.len());
+            if !non_interactive && !confirm(&prompt)? {
+                println!("削除を中止しました。");
+                return Ok(());
+            }
+            // 候補はリポジトリごとに連続して並んでいる
+            let mut removed = 0;
+            let mut rest = &candidates[..];
+            for (name, manager) in &targets {
+                let count = repos.iter().filter(|repo| *repo == name).count();
+                let (group, tail) = rest.split_at(count);
+                removed += prune::remove_candidates(manager, group, args.archive);
+                rest = tail;
+            }
+            println!(
+                "{} 件中 {} 件のワークスペースを削除しました。",
+                candidates.len(),
+                removed
+            );
+        }
+        Commands::Logs(args) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let logs = list_logs(&manager, &manager.resolve_task_name(&args.task_name)?)?;
+            if args.list {
+                print_logs(&logs);
+            } else {
+                let latest = logs.last().expect("list_logs は空の一覧を返さない");
+                show_log(&latest.path, args.follow)?;
+            }
+        }
+        Commands::Prompt(PromptCommands::Render(args)) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let tool = ToolRegistry::load(&manager.config)?.resolve(args.tool.as_deref())?;
+            let options = StartOptions {
+                plan: args.plan,
+                template: args.template,
+                record: false,
+                base_branch: None,
+                branch: None,
+                on_conflict: None,
+                interactive: false,
+            };
+            let prompt = render_start_prompt(args.issue_number, &tool, &manager, &options)?;
+            println!("{}", prompt);
+        }
+        Commands::Repos(ReposCommands::List(args)) => {
+            let registry = Registry::load()?;
+            let listings: Vec<RepoListing> = registry
+                .repos
+                .iter()
+                .map(|(name, repo)| {
+                    let config = repo.load_config().ok();
+                    let tasks = config.clone().and_then(|config| {
+                        let manager = RepoManager::new(config, repo.config.clone());
+                        manager.list_tasks().ok().map(|tasks| tasks.len())
+                    });
+                    RepoListing {
+                        name,
+                        config: &repo.config,
+                        repo_url: config.map(|config| config.repo_url),
+                        tasks,
+                    }
+                })
+                .collect();
+            print_repos(&listings, args.format)?;
+        }
+        Commands::Repos(ReposCommands::Add(args)) => {
+            let config_path = match args.path {
+                Some(path) if path.is_dir() => path.join(CONFIG_FILE),
+                Some(path) => path,
+                None => discover_config_path(None)?,
+            };
+            // aicloner の設定ファイルであることを確かめてから登録する
+            Config::load(&config_path)?;
+            let mut registry = Registry::load()?;
+            let name = registry.add(&config_path, args.name.as_deref())?;
+            registry.save()?;
+            println!("✓ リポジトリ一覧に登録しました: {}", name);
+        }
+        Commands::Repos(ReposCommands::Forget(args)) => {
+            let mut registry = Registry::load()?;
+            let repo = registry.forget(&args.name)?;
+            registry.save()?;
+            println!(
+                "✓ リポジトリ一覧から外しました: {} ({})",
+                args.name,
+                repo.config.display()
+            );
+        }
+        Commands::Config(ConfigCommands::Check(args)) => {
+            let path = discover_config_path(args.config.as_deref())?;
+            let (version, problems) = config_schema::check_file(&path)?;
+            if let Some(version) = version {
+                println!(
+                    "version {} の設定ファイルです。次に読み込むときに version {} へ移行します。",
+                    version, CONFIG_VERSION
+                );
+            }
+            if problems.is_empty() {
+                println!("✓ 問題は見つかりませんでした: {}", path.display());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("- {}", problem);
+            }
+            bail!("設定ファイルに {} 件の問題があります: {}", problems.len(), path.display());
+        }
+        Commands::Config(ConfigCommands::Get(args)) => {
+            let path = discover_config_path(args.config.as_deref())?;
+            let entries = config_edit::get(&path, &args.key)?;
+            match entries.as_slice() {
+                [entry] if entry.key == args.key => println!("{}", entry.plain_value()),
+                entries => {
+                    for entry in entries {
+                        println!("{} = {}", entry.key, entry.value);
+                    }
+                }
+            }
+        }
+        Commands::Config(ConfigCommands::Set(args)) => {
+            let path = discover_config_path(args.config.as_deref())?;
+            config_edit::set(&path, &args.key, &args.value)?;
+            println!("✓ {} を設定しました: {}", args.key, path.display());
+        }
+        Commands::Config(ConfigCommands::Unset(args)) => {
+            let path = discover_config_path(args.config.as_deref())?;
+            config_edit::unset(&path, &args.key)?;
+            println!("✓ {} を削除しました: {}", args.key, path.display());
+        }
+        Commands::Config(ConfigCommands::Edit(args)) => {
+            let path = discover_config_path(args.config.as_deref())?;
+            config_edit::edit(&path)?;
+        }
+        Commands::Config(ConfigCommands::List(args)) => {
+            let path = discover_config_path(args.config.as_deref())?;
+            let entries = config_edit::layered_entries(&path)?;
+            if args.show_origin {
+                let headers = ["KEY", "VALUE", "ORIGIN"];
+                let rows: Vec<Vec<String>> = entries
+                    .iter()
+                    .map(|entry| {
+                        vec![
+                            entry.key.clone(),
+                            entry.value.to_string(),
+                            entry.origin.to_string(),
+                        ]
+                    })
+                    .collect();
+                output::print_table(&headers, &rows);
+            } else {
+                for entry in &entries {
+                    println!("{} = {}", entry.key, entry.value);
+                }
+            }
+        }
+        Commands::Doctor(args) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let findings = doctor::diagnose(&manager)?;
+            if findings.is_empty() {
+                println!("問題は見つかりませんでした。");
+                return Ok(());
+            }
+            for finding in &findings {
+                println!("- {}", finding);
+            }
+            if args.fix {
+                doctor::fix(&manager, &findings)?;
+            } else {
+                println!("--fix を付けると状態ファイルを修復します。");
+            }
+        }
+        Commands::Repack(args) => {
+            let manager = load_manager(args.config.as_deref(), offline)?;
+            let task_names = if args.all {
+                manager
+                    .list_tasks()?
+                    .into_iter()
+                    .filter(|info| info.strategy == WorkspaceStrategy::Clone)
+                    .map(|info| info.name)
+                    .collect()
+            } else {
+                resolve_task_names(&manager, &args.task_names)?
+            };
+            for task_name in task_names {
+                manager.repack_task(&task_name)?;
+            }
         }
     }
 
     Ok(())
 }
 
-fn load_manager(path: &Path) -> Result<RepoManager> {
-    let config = Config::load(path)?;
-    Ok(RepoManager::new(config, path.to_path_buf()))
+/// `--all-repos` なら登録済みの全リポジトリ、それ以外は設定ファイルのリポジトリだけを返す
+fn target_repos(
+    all_repos: bool,
+    config: Option<&Path>,
+    offline: bool,
+) -> Result<Vec<(String, RepoManager)>> {
+    if !all_repos {
+        return Ok(vec![(String::new(), load_manager(config, offline)?)]);
+    }
+    let registry = Registry::load()?;
+    if registry.repos.is_empty() {
+        bail!("登録されているリポジトリがありません。aicloner repos add で登録してください。");
+    }
+    let mut targets = Vec::new();
+    for (name, repo) in registry.repos {
+        match repo.load_config() {
+            Ok(config) => {
+                let mut manager = RepoManager::new(config, repo.config);
+                manager.offline = offline;
+                targets.push((name, manager));
+            }
+            Err(err) => eprintln!("警告: {} をスキップしました: {:#}", name, err),
+        }
+    }
+    Ok(targets)
+}
+
+/// 各リポジトリで `collect` を実行して結果をつなげ、要素ごとのリポジトリ名と一緒に返す
+/// `--all-repos` のときは失敗したリポジトリを警告してスキップする
+fn collect_per_repo<T>(
+    targets: &[(String, RepoManager)],
+    all_repos: bool,
+    mut collect: impl FnMut(&RepoManager) -> Result<Vec<T>>,
+) -> Result<(Vec<T>, Vec<String>)> {
+    let mut items = Vec::new();
+    let mut repos = Vec::new();
+    for (name, manager) in targets {
+        match collect(manager) {
+            Ok(found) => {
+                repos.extend(std::iter::repeat_n(name.clone(), found.len()));
+                items.extend(found);
+            }
+            Err(err) if all_repos => eprintln!("警告: {} をスキップしました: {:#}", name, err),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok((items, repos))
+}
+
+/// status で表示するタスク (ワークスペースの中では、指定が無ければそのタスクだけ)
+fn status_tasks(manager: &RepoManager, names: &[String]) -> Result<Vec<TaskInfo>> {
+    let mut tasks = manager.list_tasks()?;
+    let mut task_names = resolve_task_names(manager, names)?;
+    if task_names.is_empty() {
+        task_names.extend(manager.current_task());
+    }
+    if !task_names.is_empty() {
+        for name in &task_names {
+            if !tasks.iter().any(|task| &task.name == name) {
+                bail!("タスク \"{}\" は存在しません。", name);
+            }
+        }
+        tasks.retain(|task| task_names.contains(&task.name));
+    }
+    Ok(tasks)
+}
+
+fn load_manager(path: Option<&Path>, offline: bool) -> Result<RepoManager> {
+    let path = discover_config_path(path)?;
+    let config = Config::load(&path)?;
+    let mut manager = RepoManager::new(config, path);
+    manager.offline = offline;
+    Ok(manager)
+}
+
+/// 引数のタスク名を解決する (`.` はカレントディレクトリのタスク)
+fn resolve_task_names(manager: &RepoManager, names: &[String]) -> Result<Vec<String>> {
+    names
+        .iter()
+        .map(|name| manager.resolve_task_name(name))
+        .collect()
 }
 
 fn resolve_config_path(repo_root: &Path, config: &PathBuf) -> PathBuf {
@@ -95,10 +600,7 @@ fn resolve_config_path(repo_root: &Path, config: &PathBuf) -> PathBuf {
 
 fn repo_dir_name(repo_url: &str) -> Result<String> {
     let trimmed = repo_url.trim_end_matches('/');
-    let name_part = trimmed
-        .rsplit(['/', ':'])
-        .next()
-        .unwrap_or("");
+    let name_part = trimmed.rsplit(['/', ':']).next().unwrap_or("");
     let name = name_part
         .strip_suffix(".git")
         .unwrap_or(name_part)
@@ -109,53 +611,196 @@ fn repo_dir_name(repo_url: &str) -> Result<String> {
     Ok(name)
 }
 
-fn ensure_aicloner_repo(config_path: &Path) -> Result<()> {
-    if !config_path.exists() {
-        bail!(
-            "aicloner リポジトリではありません。{} が見つかりません。\n\
-             先に 'aicloner init' を実行してください。",
-            config_path.display()
-        );
+/// `repos` を渡すと各行にリポジトリ名を付ける (`--all-repos`)
+fn print_tasks(
+    tasks: &[TaskInfo],
+    repos: Option<&[String]>,
+    format: OutputFormat,
+) -> Result<()> {
+    if format == OutputFormat::Json {
+        return match repos {
+            Some(repos) => output::print_json_with_repos(Collection::Tasks, tasks, repos),
+            None => output::print_json(Collection::Tasks, tasks),
+        };
+    }
+    let mut headers = vec![
+        "TASK", "PATH", "TYPE", "BRANCH", "ISSUE", "BASE", "TOOL", "AGE",
+    ];
+    let mut rows: Vec<Vec<String>> = tasks
+        .iter()
+        .map(|info| {
+            let record = info.record.as_ref();
+            vec![
+                info.name.clone(),
+                info.path.display().to_string(),
+                info.strategy.to_string(),
+                info.branch.clone().unwrap_or_else(|| "-".to_string()),
+                record
+                    .and_then(|r| r.issue)
+                    .map(|n| format!("#{}", n))
+                    .unwrap_or_else(|| "-".to_string()),
+                record
+                    .map(|r| r.base_branch.clone())
+                    .unwrap_or_else(|| "-".to_string()),
+                record
+                    .and_then(|r| r.tool.clone())
+                    .unwrap_or_else(|| "-".to_string()),
+                record
+                    .and_then(|r| r.age())
+                    .map(format_age)
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    if let Some(repos) = repos {
+        output::prepend_repo_column(&mut headers, &mut rows, repos);
+    }
+    match format {
+        OutputFormat::Tsv => output::print_tsv(&headers, &rows),
+        _ => output::print_table(&headers, &rows),
     }
     Ok(())
 }
 
-fn check_gh_installed() -> Result<()> {
-    let output = Command::new("gh").arg("--version").output();
+/// AI ツールを選んで Issue の対応を始める (`start` と `issues --pick` で共通)
+fn start_issue(
+    manager: RepoManager,
+    issue_number: u32,
+    tool: Option<&str>,
+    options: &StartOptions,
+) -> Result<()> {
+    let selected_tool = ToolRegistry::load(&manager.config)?.resolve(tool)?;
+    selected_tool.check_installed()?;
+    handle_start(issue_number, selected_tool, manager, options)
+}
 
-    match output {
-        Ok(output) if output.status.success() => Ok(()),
-        _ => bail!(
-            "GitHub CLI (gh) がインストールされていません。\n\
-             https://cli.github.com/ からインストールしてください。"
-        ),
-    }
+/// `issues` の 1 行 (Issue と、その Issue のワークスペース)
+#[derive(Serialize)]
+struct IssueListing<'a> {
+    #[serde(flatten)]
+    issue: &'a Issue,
+    workspaces: Vec<String>,
 }
 
-fn list_issues(manager: &RepoManager) -> Result<()> {
-    let base_dir = manager.base_dir();
+fn print_logs(logs: &[LogEntry]) {
+    let headers = ["LOG", "STARTED", "EXIT", "SIZE"];
+    let rows: Vec<Vec<String>> = logs
+        .iter()
+        .map(|log| {
+            vec![
+                log.path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                log.started.clone().unwrap_or_else(|| "-".to_string()),
+                log.exit.clone().unwrap_or_else(|| "-".to_string()),
+                log.size.to_string(),
+            ]
+        })
+        .collect();
+    output::print_table(&headers, &rows);
+}
 
-    let args = vec![
-        "issue".to_string(),
-        "list".to_string(),
-        "--state".to_string(),
-        "open".to_string(),
-    ];
+/// `repos list` の 1 行 (設定を読めないリポジトリは URL とタスク数が空)
+#[derive(Serialize)]
+struct RepoListing<'a> {
+    name: &'a str,
+    config: &'a Path,
+    repo_url: Option<String>,
+    tasks: Option<usize>,
+}
 
-    println!("実行: gh {} (cwd: {})", args.join(" "), base_dir.display());
-    let output = Command::new("gh")
-        .args(&args)
-        .current_dir(&base_dir)
-        .output()
-        .context("gh issue list の実行に失敗しました")?;
+fn print_repos(listings: &[RepoListing], format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        return output::print_json(Collection::Repos, listings);
+    }
+    let headers = ["NAME", "REPO_URL", "TASKS", "CONFIG"];
+    let rows: Vec<Vec<String>> = listings
+        .iter()
+        .map(|listing| {
+            vec![
+                listing.name.to_string(),
+                listing.repo_url.clone().unwrap_or_else(|| "-".to_string()),
+                listing
+                    .tasks
+                    .map(|n| n.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                listing.config.display().to_string(),
+            ]
+        })
+        .collect();
+    match format {
+        OutputFormat::Tsv => output::print_tsv(&headers, &rows),
+        _ => output::print_table(&headers, &rows),
+    }
+    Ok(())
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Issue一覧の取得に失敗しました: {}", stderr.trim());
+fn print_issues(
+    listings: &[IssueListing],
+    repos: Option<&[String]>,
+    format: OutputFormat,
+) -> Result<()> {
+    if format == OutputFormat::Json {
+        return match repos {
+            Some(repos) => output::print_json_with_repos(Collection::Issues, listings, repos),
+            None => output::print_json(Collection::Issues, listings),
+        };
+    }
+    let mut headers = vec!["NUMBER", "TITLE", "LABELS", "STATE", "WORKSPACE"];
+    let mut rows: Vec<Vec<String>> = listings
+        .iter()
+        .map(|listing| {
+            let issue = listing.issue;
+            vec![
+                format!("#{}", issue.number),
+                issue.title.clone(),
+                issue.labels.join(", "),
+                issue.state.clone(),
+                if listing.workspaces.is_empty() {
+                    "-".to_string()
+                } else {
+                    listing.workspaces.join(", ")
+                },
+            ]
+        })
+        .collect();
+    if let Some(repos) = repos {
+        output::prepend_repo_column(&mut headers, &mut rows, repos);
+    }
+    match format {
+        OutputFormat::Tsv => output::print_tsv(&headers, &rows),
+        _ => output::print_table(&headers, &rows),
     }
+    Ok(())
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    print!("{}", stdout);
+/// 一覧を表示して対応する Issue の番号を入力させる (空欄ならキャンセル)
+fn pick_issue(listings: &[IssueListing]) -> Result<Option<u32>> {
+    if listings.is_empty() {
+        println!("該当する Issue はありません。");
+        return Ok(None);
+    }
+    print_issues(listings, None, OutputFormat::Table)?;
 
-    Ok(())
+    print!("対応する Issue の番号を入力してください (空欄でキャンセル): ");
+    io::stdout()
+        .flush()
+        .context("プロンプトの表示に失敗しました")?;
+    let mut input = String::new();
+    io::stdin()
+        .read_line(&mut input)
+        .context("入力の読み取りに失敗しました")?;
+    let input = input.trim().trim_start_matches('#');
+    if input.is_empty() {
+        println!("キャンセルしました。");
+        return Ok(None);
+    }
+    let number: u32 = input
+        .parse()
+        .map_err(|_| anyhow!("Issue の番号を入力してください: {}", input))?;
+    if !listings.iter().any(|listing| listing.issue.number == number) {
+        bail!("Issue #{} は一覧にありません。", number);
+    }
+    Ok(Some(number))
 }
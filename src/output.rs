@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// JSON 出力のスキーマバージョン
+/// フィールドの削除・型変更など互換性の無い変更をしたときに上げる
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// list / status / issues / repos list の出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// 人が読むための表形式
+    #[default]
+    Table,
+    /// `schema_version` 付きの JSON
+    Json,
+    /// ヘッダ行付きのタブ区切り
+    Tsv,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    schema_version: u32,
+    #[serde(flatten)]
+    body: Body<'a, T>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Body<'a, T: Serialize> {
+    Tasks(&'a [T]),
+    Statuses(&'a [T]),
+    Issues(&'a [T]),
+    Repos(&'a [T]),
+}
+
+/// JSON 出力の最上位のキー
+#[derive(Debug, Clone, Copy)]
+pub enum Collection {
+    Tasks,
+    Statuses,
+    Issues,
+    Repos,
+}
+
+/// `{"schema_version": N, "<collection>": [...]}` の形で JSON を出力する
+pub fn print_json<T: Serialize>(collection: Collection, items: &[T]) -> Result<()> {
+    let body = match collection {
+        Collection::Tasks => Body::Tasks(items),
+        Collection::Statuses => Body::Statuses(items),
+        Collection::Issues => Body::Issues(items),
+        Collection::Repos => Body::Repos(items),
+    };
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        body,
+    };
+    let json = serde_json::to_string_pretty(&envelope).context("JSON の生成に失敗しました")?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// `--all-repos` で集計した 1 件 (JSON では `repo` を加える)
+#[derive(Serialize)]
+struct RepoItem<'a, T: Serialize> {
+    repo: &'a str,
+    #[serde(flatten)]
+    item: &'a T,
+}
+
+/// 各要素にリポジトリ名 (`repos` の同じ位置の値) を付けて JSON を出力する
+pub fn print_json_with_repos<T: Serialize>(
+    collection: Collection,
+    items: &[T],
+    repos: &[String],
+) -> Result<()> {
+    let items: Vec<RepoItem<T>> = items
+        .iter()
+        .zip(repos)
+        .map(|(item, repo)| RepoItem { repo, item })
+        .collect();
+    print_json(collection, &items)
+}
+
+/// 表の先頭に REPO 列を加える
+pub fn prepend_repo_column(headers: &mut Vec<&str>, rows: &mut [Vec<String>], repos: &[String]) {
+    headers.insert(0, "REPO");
+    for (row, repo) in rows.iter_mut().zip(repos) {
+        row.insert(0, repo.clone());
+    }
+}
+
+/// ヘッダ行付きのタブ区切りで出力する (値中のタブ・改行は空白に置き換える)
+pub fn print_tsv(headers: &[&str], rows: &[Vec<String>]) {
+    println!("{}", headers.join("\t"));
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|cell| cell.replace(['\t', '\n', '\r'], " "))
+            .collect();
+        println!("{}", cells.join("\t"));
+    }
+}
+
+/// 列幅を内容に合わせて揃えた表を出力する
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| display_width(h)).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    print_row(&headers, &widths);
+    for row in rows {
+        print_row(row, &widths);
+    }
+}
+
+fn print_row(cells: &[String], widths: &[usize]) {
+    let last = cells.len().saturating_sub(1);
+    let mut line = String::new();
+    for (i, (cell, width)) in cells.iter().zip(widths).enumerate() {
+        line.push_str(cell);
+        if i != last {
+            let pad = width.saturating_sub(display_width(cell));
+            line.push_str(&" ".repeat(pad + 2));
+        }
+    }
+    println!("{}", line.trim_end());
+}
+
+/// 端末上の表示幅 (全角文字を 2 として数える)
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if is_wide(c) { 2 } else { 1 }).sum()
+}
+
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x20000..=0x3FFFD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("task-a"), 6);
+        assert_eq!(display_width("最新"), 4);
+        assert_eq!(display_width("3h前 fix"), 8);
+    }
+
+    #[test]
+    fn test_json_envelope() {
+        let envelope = Envelope {
+            schema_version: SCHEMA_VERSION,
+            body: Body::Tasks(&["a", "b"]),
+        };
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["schema_version"], SCHEMA_VERSION);
+        assert_eq!(json["tasks"], serde_json::json!(["a", "b"]));
+    }
+}
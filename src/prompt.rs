@@ -0,0 +1,427 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    config::{user_config_dir, PromptConfig},
+    issue::Issue,
+    repo::RepoManager,
+};
+
+/// `start` が使うテンプレート名
+pub const DEFAULT_TEMPLATE: &str = "start";
+/// `resume` が使うテンプレート名
+pub const RESUME_TEMPLATE: &str = "resume";
+
+/// 組み込みのテンプレート
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("start", include_str!("prompts/start.md")),
+    ("resume", include_str!("prompts/resume.md")),
+];
+
+/// テンプレートに渡す変数
+#[derive(Debug, Clone, Default)]
+pub struct PromptContext {
+    pub issue_number: u32,
+    pub issue_title: String,
+    pub issue_body: String,
+    /// カンマ区切りのラベル名
+    pub issue_labels: String,
+    /// カンマ区切りの担当者
+    pub issue_assignees: String,
+    /// 整形・省略済みのコメント
+    pub issue_comments: String,
+    /// タイトル・ラベル・本文・コメントをまとめた Markdown (Issue を取得できなければ空)
+    pub issue: String,
+    pub branch: String,
+    pub base_branch: String,
+    /// `owner/repo` 形式のリポジトリ名
+    pub repo: String,
+    pub tool: String,
+    pub plan: bool,
+    /// ベースブランチからのコミット (`git log --oneline`、resume のみ)
+    pub git_log: String,
+    /// 作業ツリーの状態 (`git status --short --branch`、resume のみ)
+    pub git_status: String,
+}
+
+impl PromptContext {
+    fn variables(&self) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("issue_number", self.issue_number.to_string()),
+            ("issue_title", self.issue_title.clone()),
+            ("issue_body", self.issue_body.clone()),
+            ("issue_labels", self.issue_labels.clone()),
+            ("issue_assignees", self.issue_assignees.clone()),
+            ("issue_comments", self.issue_comments.clone()),
+            ("issue", self.issue.clone()),
+            ("branch", self.branch.clone()),
+            ("base_branch", self.base_branch.clone()),
+            ("repo", self.repo.clone()),
+            ("tool", self.tool.clone()),
+            ("plan", if self.plan { "true" } else { "" }.to_string()),
+            ("git_log", self.git_log.clone()),
+            ("git_status", self.git_status.clone()),
+        ])
+    }
+
+    /// 取得した Issue の内容を変数に設定する (長い本文やコメントは `limits` に従って省略する)
+    pub fn set_issue(&mut self, issue: &Issue, limits: &PromptConfig) {
+        self.issue_title = issue.title.clone();
+        self.issue_body = truncate_chars(issue.body.trim(), limits.max_chars);
+        self.issue_labels = issue.labels.join(", ");
+        self.issue_assignees = issue.assignees.join(", ");
+
+        let skipped = issue.comments.len().saturating_sub(limits.max_comments);
+        let mut comments = Vec::new();
+        if skipped > 0 {
+            comments.push(format!("（古いコメント {} 件を省略）", skipped));
+        }
+        for comment in &issue.comments[skipped..] {
+            comments.push(format!(
+                "### @{} ({})\n\n{}",
+                comment.author,
+                comment.created_at,
+                truncate_chars(comment.body.trim(), limits.max_chars)
+            ));
+        }
+        self.issue_comments = comments.join("\n\n");
+
+        let mut block = vec![format!("## #{} {}", issue.number, issue.title)];
+        let mut meta = Vec::new();
+        if !self.issue_labels.is_empty() {
+            meta.push(format!("ラベル: {}", self.issue_labels));
+        }
+        if !self.issue_assignees.is_empty() {
+            meta.push(format!("担当者: {}", self.issue_assignees));
+        }
+        if !meta.is_empty() {
+            block.push(meta.join("\n"));
+        }
+        if !self.issue_body.is_empty() {
+            block.push(self.issue_body.clone());
+        }
+        if !self.issue_comments.is_empty() {
+            block.push(format!("## コメント\n\n{}", self.issue_comments));
+        }
+        self.issue = block.join("\n\n");
+    }
+}
+
+/// `max_chars` 文字を超える部分を省略する
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars).collect();
+    format!("{}\n…（以下 {} 文字省略）", kept, total - max_chars)
+}
+
+/// テンプレートの読み込み元
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSource {
+    File(PathBuf),
+    Builtin,
+}
+
+/// テンプレートを探す
+/// `.aicloner/prompts/<name>.md` > ユーザー設定の `prompts/<name>.md` > 組み込み の順に使う
+pub fn load_template(manager: &RepoManager, name: &str) -> Result<(String, TemplateSource)> {
+    let file_name = format!("{}.md", name);
+    let candidates = [
+        Some(manager.state_dir().join("prompts")),
+        user_config_dir().map(|dir| dir.join("prompts")),
+    ];
+    for dir in candidates.into_iter().flatten() {
+        let path = dir.join(&file_name);
+        if path.exists() {
+            let template = fs::read_to_string(&path).with_context(|| {
+                format!("テンプレートの読み込みに失敗しました: {}", path.display())
+            })?;
+            return Ok((template, TemplateSource::File(path)));
+        }
+    }
+    match BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+    {
+        Some((_, template)) => Ok((template.to_string(), TemplateSource::Builtin)),
+        None => bail!(
+            "テンプレート \"{}\" が見つかりません。.aicloner/prompts/{} を作成してください。",
+            name,
+            file_name
+        ),
+    }
+}
+
+/// テンプレートを探して描画する
+pub fn render_prompt(manager: &RepoManager, name: &str, context: &PromptContext) -> Result<String> {
+    let (template, source) = load_template(manager, name)?;
+    render(&template, context).with_context(|| match source {
+        TemplateSource::File(path) => {
+            format!("テンプレートの描画に失敗しました: {}", path.display())
+        }
+        TemplateSource::Builtin => {
+            format!("組み込みテンプレート \"{}\" の描画に失敗しました", name)
+        }
+    })
+}
+
+/// テンプレートを描画する
+///
+/// - `{{name}}`: 変数の値に置き換える
+/// - `{{#if name}}...{{else}}...{{/if}}`: 変数が空でなければ前半、空なら後半を出力する
+pub fn render(template: &str, context: &PromptContext) -> Result<String> {
+    let nodes = parse(template)?;
+    let variables = context.variables();
+    let mut output = String::new();
+    render_nodes(&nodes, &variables, &mut output)?;
+    Ok(output.trim_end().to_string())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Var(String),
+    If {
+        name: String,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+fn parse(template: &str) -> Result<Vec<Node>> {
+    // 入れ子の `{{#if}}` を扱うため、開いているブロックの外側の内容をスタックに積む
+    struct Block {
+        name: String,
+        outer: Vec<Node>,
+        then: Vec<Node>,
+        in_else: bool,
+    }
+
+    let mut stack: Vec<Block> = Vec::new();
+    let mut current: Vec<Node> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            current.push(Node::Text(rest[..start].to_string()));
+        }
+        let Some(end) = rest[start..].find("}}") else {
+            bail!("閉じられていないタグがあります: {}", &rest[start..]);
+        };
+        let tag = rest[start + 2..start + end].trim();
+        rest = &rest[start + end + 2..];
+
+        if let Some(name) = tag.strip_prefix("#if ") {
+            stack.push(Block {
+                name: name.trim().to_string(),
+                outer: std::mem::take(&mut current),
+                then: Vec::new(),
+                in_else: false,
+            });
+        } else if tag == "else" {
+            let Some(block) = stack.last_mut().filter(|block| !block.in_else) else {
+                bail!("対応する {{{{#if}}}} の無い {{{{else}}}} があります");
+            };
+            block.then = std::mem::take(&mut current);
+            block.in_else = true;
+        } else if tag == "/if" {
+            let Some(block) = stack.pop() else {
+                bail!("対応する {{{{#if}}}} の無い {{{{/if}}}} があります");
+            };
+            let body = std::mem::replace(&mut current, block.outer);
+            let (then, otherwise) = if block.in_else {
+                (block.then, body)
+            } else {
+                (body, Vec::new())
+            };
+            current.push(Node::If {
+                name: block.name,
+                then,
+                otherwise,
+            });
+        } else if is_identifier(tag) {
+            current.push(Node::Var(tag.to_string()));
+        } else {
+            bail!("解釈できないタグです: {{{{{}}}}}", tag);
+        }
+    }
+    if !rest.is_empty() {
+        current.push(Node::Text(rest.to_string()));
+    }
+    if let Some(block) = stack.last() {
+        bail!("{{{{#if {}}}}} が閉じられていません", block.name);
+    }
+    Ok(current)
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    variables: &BTreeMap<&'static str, String>,
+    output: &mut String,
+) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Var(name) => output.push_str(lookup(variables, name)?),
+            Node::If {
+                name,
+                then,
+                otherwise,
+            } => {
+                let branch = if lookup(variables, name)?.is_empty() {
+                    otherwise
+                } else {
+                    then
+                };
+                render_nodes(branch, variables, output)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn lookup<'a>(variables: &'a BTreeMap<&'static str, String>, name: &str) -> Result<&'a str> {
+    match variables.get(name) {
+        Some(value) => Ok(value),
+        None => bail!(
+            "未定義の変数です: {} (使える変数: {})",
+            name,
+            variables.keys().copied().collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+fn is_identifier(tag: &str) -> bool {
+    !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// リポジトリ URL から `owner/repo` を取り出す
+pub fn repo_slug(repo_url: &str) -> String {
+    let trimmed = repo_url.trim_end_matches('/');
+    let trimmed = trimmed.strip_suffix(".git").unwrap_or(trimmed);
+    let parts: Vec<&str> = trimmed.rsplit(['/', ':']).take(2).collect();
+    match parts.as_slice() {
+        [repo, owner] if !owner.is_empty() => format!("{}/{}", owner, repo),
+        [repo, ..] => repo.to_string(),
+        [] => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(plan: bool) -> PromptContext {
+        PromptContext {
+            issue_number: 42,
+            issue_title: "Fix login".to_string(),
+            branch: "aicloner-issue42".to_string(),
+            base_branch: "main".to_string(),
+            repo: "owner/repo".to_string(),
+            plan,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_variables_and_conditionals() {
+        let template = "#{{issue_number}} {{ issue_title }} on {{repo}}\n\
+                        {{#if plan}}plan{{#if issue_body}} with body{{/if}}{{else}}go{{/if}}\n";
+        assert_eq!(
+            render(template, &context(true)).unwrap(),
+            "#42 Fix login on owner/repo\nplan"
+        );
+        assert_eq!(
+            render(template, &context(false)).unwrap(),
+            "#42 Fix login on owner/repo\ngo"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        assert!(render("{{unknown}}", &context(false)).is_err());
+        assert!(render("{{#if plan}}open", &context(false)).is_err());
+        assert!(render("{{/if}}", &context(false)).is_err());
+        assert!(render("{{issue_number", &context(false)).is_err());
+    }
+
+    #[test]
+    fn test_builtin_template_matches_previous_prompt() {
+        let (name, template) = BUILTIN_TEMPLATES[0];
+        assert_eq!(name, DEFAULT_TEMPLATE);
+        let plan = render(template, &context(true)).unwrap();
+        assert!(plan.starts_with(
+            "あなたは優秀なエンジニアです。issue#42を対応してください。まずplanモードで"
+        ));
+        assert!(plan.contains("Closes #42を本文に含める"));
+        let normal = render(template, &context(false)).unwrap();
+        assert!(normal
+            .starts_with("あなたは優秀なエンジニアです。issue#42を対応してください。\n\n- gh"));
+    }
+
+    #[test]
+    fn test_builtin_resume_template() {
+        let (name, template) = BUILTIN_TEMPLATES[1];
+        assert_eq!(name, RESUME_TEMPLATE);
+        let mut context = context(false);
+        let empty = render(template, &context).unwrap();
+        assert!(empty.contains("ブランチ `aicloner-issue42` で進めていた作業を再開"));
+        assert!(empty.contains("まだコミットはありません。"));
+
+        context.git_log = "abc1234 ログイン修正".to_string();
+        context.git_status = "## aicloner-issue42\n M src/login.rs".to_string();
+        let resumed = render(template, &context).unwrap();
+        assert!(resumed.contains("```\nabc1234 ログイン修正\n```"));
+        assert!(resumed.contains(" M src/login.rs"));
+    }
+
+    #[test]
+    fn test_set_issue_truncates_comments() {
+        let issue: Issue = serde_json::from_value(serde_json::json!({
+            "number": 42,
+            "title": "Fix login",
+            "body": "0123456789",
+            "state": "OPEN",
+            "labels": [{"name": "bug"}],
+            "assignees": [{"login": "alice"}],
+            "comments": [
+                {"author": {"login": "bob"}, "body": "first", "createdAt": "2024-05-01"},
+                {"author": {"login": "carol"}, "body": "second", "createdAt": "2024-05-02"},
+                {"author": {"login": "dave"}, "body": "third", "createdAt": "2024-05-03"}
+            ]
+        }))
+        .unwrap();
+        let limits = PromptConfig {
+            max_comments: 2,
+            max_chars: 4,
+        };
+        let mut context = context(false);
+        context.set_issue(&issue, &limits);
+
+        assert_eq!(context.issue_body, "0123\n…（以下 6 文字省略）");
+        assert_eq!(context.issue_labels, "bug");
+        assert!(context
+            .issue_comments
+            .starts_with("（古いコメント 1 件を省略）"));
+        assert!(!context.issue_comments.contains("first"));
+        assert!(context
+            .issue_comments
+            .contains("### @dave (2024-05-03)\n\nthir"));
+        assert!(context
+            .issue
+            .starts_with("## #42 Fix login\n\nラベル: bug\n担当者: alice"));
+
+        let rendered = render(BUILTIN_TEMPLATES[0].1, &context).unwrap();
+        assert!(rendered.contains("## #42 Fix login"));
+        assert!(!rendered.contains("ghコマンドを使ってissueを確認すること"));
+    }
+
+    #[test]
+    fn test_repo_slug() {
+        assert_eq!(repo_slug("git@github.com:owner/repo.git"), "owner/repo");
+        assert_eq!(repo_slug("https://github.com/owner/repo/"), "owner/repo");
+        assert_eq!(repo_slug("repo"), "repo");
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    fmt, fs,
+    io::Write,
+    process::{Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    forge::{self, Forge},
+    output,
+    repo::{run_command_quiet, RemoveOptions, RepoManager, TaskInfo},
+    state::format_age,
+};
+
+/// prune の実行オプション
+pub struct PruneOptions {
+    /// 作成からこの期間以上経過したタスクだけを対象にする
+    pub older_than: Option<Duration>,
+    /// forge で PR と Issue の状態を確認する
+    pub check_forge: bool,
+    /// 判定の前にベースブランチを取得する (`--dry-run` では何も書き込まない)
+    pub fetch: bool,
+}
+
+/// 削除してよいと判断した理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PruneReason {
+    /// ブランチの先端がベースブランチに含まれている
+    Merged,
+    /// 同じ変更を持つコミットがベースブランチにある (squash merge)
+    SquashMerged,
+    PullRequestMerged(u32),
+    IssueClosed(u32),
+}
+
+impl fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PruneReason::Merged => f.pad("マージ済み"),
+            PruneReason::SquashMerged => f.pad("マージ済み (squash)"),
+            PruneReason::PullRequestMerged(number) => f.pad(&format!("PR #{} マージ済み", number)),
+            PruneReason::IssueClosed(number) => f.pad(&format!("Issue #{} クローズ済み", number)),
+        }
+    }
+}
+
+pub struct PruneCandidate {
+    pub task: TaskInfo,
+    pub base_branch: String,
+    pub reason: PruneReason,
+    pub age: Option<Duration>,
+}
+
+/// 削除対象のタスクを探す
+pub fn find_candidates(
+    manager: &RepoManager,
+    options: &PruneOptions,
+) -> Result<Vec<PruneCandidate>> {
+    let forge = forge::for_manager(manager);
+    let check_forge = options.check_forge
+        && !(manager.offline && forge.is_remote())
+        && forge.check_available().is_ok();
+    let default_base = manager.default_branch()?;
+    // ミラーが無いオフライン環境では手元のリモート追跡ブランチで判定する
+    let source = options.fetch.then(|| manager.fetch_source().ok()).flatten();
+
+    let mut candidates = Vec::new();
+    for task in manager.list_tasks()? {
+        let age = task_age(&task);
+        if let Some(older_than) = options.older_than {
+            if age.is_none_or(|age| age < older_than) {
+                continue;
+            }
+        }
+        let base_branch = task
+            .record
+            .as_ref()
+            .map(|record| record.base_branch.clone())
+            .unwrap_or_else(|| default_base.clone());
+
+        let reason = match merge_reason(&task, &base_branch, source.as_deref()) {
+            Ok(Some(reason)) => Some(reason),
+            Ok(None) if check_forge => forge_reason(forge.as_ref(), &task),
+            Ok(None) => None,
+            Err(err) => {
+                eprintln!("警告: {} の判定に失敗しました: {:#}", task.name, err);
+                None
+            }
+        };
+        if let Some(reason) = reason {
+            candidates.push(PruneCandidate {
+                task,
+                base_branch,
+                reason,
+                age,
+            });
+        }
+    }
+    Ok(candidates)
+}
+
+/// `repos` を渡すと各行にリポジトリ名を付ける (`--all-repos`)
+pub fn print_candidates(candidates: &[PruneCandidate], repos: Option<&[String]>) {
+    let mut headers = vec!["TASK", "BRANCH", "BASE", "REASON", "AGE"];
+    let mut rows: Vec<Vec<String>> = candidates
+        .iter()
+        .map(|candidate| {
+            vec![
+                candidate.task.name.clone(),
+                candidate
+                    .task
+                    .branch
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+                candidate.base_branch.clone(),
+                candidate.reason.to_string(),
+                candidate
+                    .age
+                    .map(format_age)
+                    .unwrap_or_else(|| "-".to_string()),
+            ]
+        })
+        .collect();
+    if let Some(repos) = repos {
+        output::prepend_repo_column(&mut headers, &mut rows, repos);
+    }
+    output::print_table(&headers, &rows);
+}
+
+/// 候補を rm と同じ安全確認付きで削除し、削除した件数を返す
+/// 失われる作業があるタスクは削除せずにスキップする
+pub fn remove_candidates(
+    manager: &RepoManager,
+    candidates: &[PruneCandidate],
+    archive: bool,
+) -> usize {
+    let options = RemoveOptions {
+        force: false,
+        archive,
+        yes: true,
+    };
+    let mut removed = 0;
+    for candidate in candidates {
+        match manager.remove_task_clone(&candidate.task.name, &options) {
+            Ok(()) => removed += 1,
+            Err(err) => eprintln!(
+                "スキップしました: {}\n  {}",
+                candidate.task.name,
+                format!("{:#}", err).replace('\n', "\n  ")
+            ),
+        }
+    }
+    removed
+}
+
+/// 作成からの経過時間 (記録が無ければディレクトリの更新日時から求める)
+fn task_age(task: &TaskInfo) -> Option<Duration> {
+    if let Some(age) = task.record.as_ref().and_then(|record| record.age()) {
+        return Some(age);
+    }
+    let modified = fs::metadata(&task.path)
+        .and_then(|meta| meta.modified())
+        .ok()?;
+    SystemTime::now().duration_since(modified).ok()
+}
+
+/// git の履歴からベースブランチに取り込まれたかを判定する
+fn merge_reason(
+    task: &TaskInfo,
+    base_branch: &str,
+    source: Option<&str>,
+) -> Result<Option<PruneReason>> {
+    let Some(branch) = task.branch.as_deref().filter(|branch| *branch != "HEAD") else {
+        return Ok(None);
+    };
+    let git = |args: &[&str]| {
+        let mut full = vec!["-C".to_string(), task.path.display().to_string()];
+        full.extend(args.iter().map(|arg| arg.to_string()));
+        run_command_quiet("git", &full, None)
+    };
+
+    // worktree では base とリモート追跡ブランチを共有するため、base 側も更新される
+    if let Some(source) = source {
+        let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", base_branch);
+        git(&["fetch", "--quiet", source, &refspec])?;
+    }
+    let upstream = format!("origin/{}", base_branch);
+    let tip = git(&["rev-parse", branch])?;
+
+    if git(&["merge-base", "--is-ancestor", &tip, &upstream]).is_ok() {
+        return Ok(has_own_work(task, &git, &tip).then_some(PruneReason::Merged));
+    }
+
+    // ブランチ全体の差分と同じ patch-id のコミットがベースブランチにあるかを調べる
+    // (オブジェクトを書き込まないよう、コミットを作らずに差分から直接求める)
+    let merge_base = git(&["merge-base", &upstream, &tip])?;
+    let diff = git(&["diff", "--no-color", "--no-ext-diff", &merge_base, &tip])?;
+    let Some(branch_id) = patch_ids(task, &diff)?.into_iter().next() else {
+        return Ok(None);
+    };
+    let range = format!("{}..{}", merge_base, upstream);
+    let log = git(&[
+        "log",
+        "-p",
+        "--no-merges",
+        "--no-color",
+        "--no-ext-diff",
+        &range,
+    ])?;
+    if patch_ids(task, &log)?.contains(&branch_id) {
+        return Ok(Some(PruneReason::SquashMerged));
+    }
+    Ok(None)
+}
+
+/// 差分を `git patch-id --stable` に渡し、パッチごとの patch-id を返す
+fn patch_ids(task: &TaskInfo, patch: &str) -> Result<Vec<String>> {
+    if patch.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(&task.path)
+        .args(["patch-id", "--stable"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("コマンドの起動に失敗しました: git")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // run_command_quiet が末尾の改行を落とすため補う
+        writeln!(stdin, "{}", patch)?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("git patch-id が失敗しました: {}", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .map(str::to_string)
+        .collect())
+}
+
+/// ベースブランチに含まれる先端が、作成後に積まれたコミットかどうか
+/// (作成直後で何もコミットしていないタスクをマージ済みと誤判定しないため)
+fn has_own_work(task: &TaskInfo, git: &dyn Fn(&[&str]) -> Result<String>, tip: &str) -> bool {
+    let record = task.record.as_ref();
+    if let Some(base_commit) = record.and_then(|record| record.base_commit.as_deref()) {
+        return base_commit != tip;
+    }
+    // 作成時のコミットが記録されていない場合はコミット日時で判断する
+    let created = record
+        .and_then(|record| humantime::parse_rfc3339(&record.created_at).ok())
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+    let committed = git(&["log", "-1", "--format=%ct", tip])
+        .ok()
+        .and_then(|output| output.trim().parse::<u64>().ok());
+    match (created, committed) {
+        (Some(created), Some(committed)) => committed > created.as_secs(),
+        _ => false,
+    }
+}
+
+/// forge で PR がマージされたか、Issue がクローズされたかを判定する
+fn forge_reason(forge: &dyn Forge, task: &TaskInfo) -> Option<PruneReason> {
+    if let Some(branch) = &task.branch {
+        if let Ok(Some(pr)) = forge.find_pull_request(branch) {
+            if pr.state == "MERGED" {
+                return Some(PruneReason::PullRequestMerged(pr.number));
+            }
+        }
+    }
+    let issue = task.record.as_ref().and_then(|record| record.issue)?;
+    let view = forge.view_issue(issue).ok()?;
+    (view.state == "CLOSED").then_some(PruneReason::IssueClosed(view.number))
+}
@@ -0,0 +1,154 @@
+use std::{
+    fs::File,
+    io::{self, ErrorKind, IsTerminal, Read, Write},
+    os::{
+        fd::{FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    process::{Command, ExitStatus, Stdio},
+    ptr, thread,
+};
+
+use anyhow::{Context, Result};
+
+use crate::session_log::SessionLog;
+
+/// 擬似端末の上でコマンドを実行し、端末に表示しながら出力を `log` に記録する
+///
+/// `input` を渡したときは標準入力をパイプにして書き込み、それ以外は端末の入力を転送する。
+pub fn run_in_pty(
+    mut command: Command,
+    input: Option<&str>,
+    log: &mut SessionLog,
+) -> Result<ExitStatus> {
+    let (master, slave) = open_pty()?;
+    let stdin = match input {
+        Some(_) => Stdio::piped(),
+        None => Stdio::from(slave.try_clone()?),
+    };
+    command
+        .stdin(stdin)
+        .stdout(Stdio::from(slave.try_clone()?))
+        .stderr(Stdio::from(slave));
+    // SAFETY: fork 後の子プロセスでは async-signal-safe な関数だけを呼ぶ
+    unsafe {
+        command.pre_exec(|| {
+            // 新しいセッションを作り、擬似端末を制御端末にする
+            if libc::setsid() == -1 || libc::ioctl(1, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = command.spawn().context("コマンドの起動に失敗しました")?;
+    // 子プロセスに渡した slave を閉じないと、終了後も master の読み込みが終わらない
+    drop(command);
+
+    let _raw_mode = match input {
+        Some(input) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(input.as_bytes())
+                    .context("プロンプトの書き込みに失敗しました")?;
+            }
+            None
+        }
+        None => {
+            let mut writer = File::from(master.try_clone()?);
+            // 端末の入力をそのまま転送する (終了時に読み込み中でも待たない)
+            thread::spawn(move || {
+                let mut buf = [0u8; 1024];
+                let mut stdin = io::stdin();
+                while let Ok(n) = stdin.read(&mut buf) {
+                    if n == 0 || writer.write_all(&buf[..n]).is_err() {
+                        break;
+                    }
+                }
+            });
+            RawMode::enable()
+        }
+    };
+
+    let mut reader = File::from(master);
+    let mut stdout = io::stdout();
+    let mut buf = [0u8; 4096];
+    loop {
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                stdout.write_all(&buf[..n])?;
+                stdout.flush()?;
+                log.write_transcript(&buf[..n])?;
+            }
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            // 子プロセスが端末を閉じると EIO になる
+            Err(_) => break,
+        }
+    }
+    Ok(child.wait()?)
+}
+
+/// 端末と同じ大きさの擬似端末を開く
+fn open_pty() -> Result<(OwnedFd, OwnedFd)> {
+    let mut master = -1;
+    let mut slave = -1;
+    // SAFETY: winsize は C の構造体で、ゼロ埋めは有効な値
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    // SAFETY: TIOCGWINSZ は size に書き込むだけ
+    let has_size = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+    let size_ptr = if has_size {
+        &mut size as *mut libc::winsize
+    } else {
+        ptr::null_mut()
+    };
+    // SAFETY: 出力先はすべて有効なポインタか null
+    let rc = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            size_ptr,
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::last_os_error()).context("擬似端末を開けませんでした");
+    }
+    // SAFETY: openpty が成功したので、どちらも所有者のいない有効な fd
+    Ok(unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) })
+}
+
+/// 端末を raw モードにし、drop 時に元の設定へ戻す
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> Option<Self> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        // SAFETY: termios はゼロ埋めしてから tcgetattr で埋める
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+            Some(Self { original })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: enable で取得した設定に戻すだけ
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
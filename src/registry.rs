@@ -0,0 +1,145 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{user_config_dir, Config};
+
+pub const REGISTRY_FILE: &str = "repos.toml";
+
+/// ユーザー単位で管理するリポジトリの一覧 (`~/.config/aicloner/repos.toml`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Registry {
+    #[serde(default)]
+    pub repos: BTreeMap<String, RegisteredRepo>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredRepo {
+    /// リポジトリの `.aicloner.toml` (絶対パス)
+    pub config: PathBuf,
+}
+
+impl Registry {
+    pub fn path() -> Option<PathBuf> {
+        user_config_dir().map(|dir| dir.join(REGISTRY_FILE))
+    }
+
+    /// 一覧を読み込む。存在しない場合は空の一覧を返す
+    pub fn load() -> Result<Registry> {
+        let Some(path) = Self::path().filter(|path| path.exists()) else {
+            return Ok(Registry::default());
+        };
+        let raw = fs::read_to_string(&path).with_context(|| {
+            format!("リポジトリ一覧の読み込みに失敗しました: {}", path.display())
+        })?;
+        toml::from_str(&raw)
+            .with_context(|| format!("リポジトリ一覧のパースに失敗しました: {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = Self::path() else {
+            bail!("ユーザー設定のディレクトリが分かりません (HOME が未設定です)");
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "ユーザー設定ディレクトリの作成に失敗しました: {}",
+                    parent.display()
+                )
+            })?;
+        }
+        let content =
+            toml::to_string_pretty(self).context("リポジトリ一覧のシリアライズに失敗しました")?;
+        fs::write(&path, content)
+            .with_context(|| format!("リポジトリ一覧の書き込みに失敗しました: {}", path.display()))
+    }
+
+    /// 設定ファイルを登録し、登録名を返す
+    /// 登録済みならその名前を返す。名前を省略するとディレクトリ名 (重複すれば `-2` などを付ける) にする
+    pub fn add(&mut self, config_path: &Path, name: Option<&str>) -> Result<String> {
+        let config = config_path
+            .canonicalize()
+            .with_context(|| format!("設定ファイルが見つかりません: {}", config_path.display()))?;
+        if let Some((existing, _)) = self.repos.iter().find(|(_, repo)| repo.config == config) {
+            return Ok(existing.clone());
+        }
+
+        let name = match name {
+            Some(name) if self.repos.contains_key(name) => {
+                bail!(
+                    "\"{}\" は既に別のリポジトリの名前として登録されています",
+                    name
+                )
+            }
+            Some(name) => name.to_string(),
+            None => {
+                let base = config
+                    .parent()
+                    .and_then(|dir| dir.file_name())
+                    .and_then(|name| name.to_str())
+                    .unwrap_or("repo")
+                    .to_string();
+                (1..)
+                    .map(|n| match n {
+                        1 => base.clone(),
+                        n => format!("{}-{}", base, n),
+                    })
+                    .find(|name| !self.repos.contains_key(name))
+                    .expect("連番の候補は尽きない")
+            }
+        };
+        self.repos.insert(name.clone(), RegisteredRepo { config });
+        Ok(name)
+    }
+
+    /// 登録を解除する (ファイルは削除しない)
+    pub fn forget(&mut self, name: &str) -> Result<RegisteredRepo> {
+        match self.repos.remove(name) {
+            Some(repo) => Ok(repo),
+            None => bail!("リポジトリ \"{}\" は登録されていません", name),
+        }
+    }
+}
+
+impl RegisteredRepo {
+    pub fn load_config(&self) -> Result<Config> {
+        Config::load(&self.config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_forget() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let config_in = |dir: &str| {
+            let dir = tmp.path().join(dir);
+            fs::create_dir_all(&dir).unwrap();
+            let path = dir.join(".aicloner.toml");
+            fs::write(&path, "repo_url = \"x\"\n").unwrap();
+            path
+        };
+        let api = config_in("api");
+        let other_api = config_in("other/api");
+
+        let mut registry = Registry::default();
+        assert_eq!(registry.add(&api, None).unwrap(), "api");
+        assert_eq!(registry.add(&api, None).unwrap(), "api");
+        assert_eq!(registry.add(&other_api, None).unwrap(), "api-2");
+        assert!(registry.add(&config_in("web"), Some("api")).is_err());
+        assert_eq!(registry.repos.len(), 2);
+
+        assert_eq!(
+            registry.forget("api").unwrap().config,
+            api.canonicalize().unwrap()
+        );
+        assert!(registry.forget("api").is_err());
+    }
+}
@@ -1,17 +1,25 @@
 use std::{
-    fs,
-    io::{self, Write},
+    env, fs,
+    io::{self, IsTerminal, Write},
     path::{Path, PathBuf},
     process::Command,
 };
 
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 
-use crate::config::Config;
+use crate::{
+    config::{Config, WorkspaceStrategy},
+    safety::{self, WorkspaceInspection},
+    state::{self, State, TaskRecord, STATE_DIR, STATE_FILE},
+    workspace::{self, WorkspaceRequest},
+};
 
 pub struct RepoManager {
     pub config: Config,
     pub config_path: PathBuf,
+    /// ネットワークを使うコマンドを実行せずに即座に失敗させる
+    pub offline: bool,
 }
 
 impl RepoManager {
@@ -19,6 +27,7 @@ impl RepoManager {
         Self {
             config,
             config_path,
+            offline: false,
         }
     }
 
@@ -30,11 +39,184 @@ impl RepoManager {
         self.resolve_path(&self.config.workspaces_dir)
     }
 
+    pub fn mirror_dir(&self) -> PathBuf {
+        self.resolve_path(&self.config.mirror_dir)
+    }
+
+    /// `.aicloner/` ディレクトリ (状態ファイルなどの置き場所)
+    pub fn state_dir(&self) -> PathBuf {
+        self.resolve_path(STATE_DIR)
+    }
+
+    /// `rm --archive` の退避先
+    pub fn trash_dir(&self) -> PathBuf {
+        self.state_dir().join("trash")
+    }
+
+    /// ヘッドレス実行したセッションのログの置き場所 (`<task>/<時刻>.log`)
+    pub fn logs_dir(&self) -> PathBuf {
+        self.state_dir().join("logs")
+    }
+
+    pub fn state_path(&self) -> PathBuf {
+        self.state_dir().join(STATE_FILE)
+    }
+
+    pub fn load_state(&self) -> Result<State> {
+        State::load(&self.state_path())
+    }
+
+    pub fn save_state(&self, state: &State) -> Result<()> {
+        state.save(&self.state_path())
+    }
+
+    /// タスクのメタデータを更新する (記録が無いタスクは何もしない)
+    pub fn update_task_record(
+        &self,
+        task_name: &str,
+        update: impl FnOnce(&mut TaskRecord),
+    ) -> Result<()> {
+        let mut state = self.load_state()?;
+        if let Some(record) = state.tasks.get_mut(task_name) {
+            update(record);
+            self.save_state(&state)?;
+        }
+        Ok(())
+    }
+
+    /// オフラインモードであればネットワーク操作を実行せずにエラーにする
+    pub fn ensure_online(&self, action: &str) -> Result<()> {
+        if self.offline {
+            bail!(
+                "オフラインモードのため{}は実行できません。\n\
+                 --offline を外して再実行してください。",
+                action
+            );
+        }
+        Ok(())
+    }
+
+    /// clone や fetch の取得元 (ミラーがあればミラー、無ければリモート)
+    pub fn fetch_source(&self) -> Result<String> {
+        let mirror_dir = self.mirror_dir();
+        if mirror_dir.exists() {
+            let mirror = std::path::absolute(&mirror_dir).with_context(|| {
+                format!("ミラーのパス解決に失敗しました: {}", mirror_dir.display())
+            })?;
+            return Ok(mirror.display().to_string());
+        }
+        if self.offline {
+            bail!(
+                "オフラインモードですがミラーがありません: {}\n\
+                 オンライン時に 'aicloner fetch' でミラーを作成してください。",
+                mirror_dir.display()
+            );
+        }
+        Ok(self.config.repo_url.clone())
+    }
+
+    /// ミラーから取得したリポジトリの origin を本来のリモートに向け直す
+    pub(crate) fn point_origin_upstream(&self, repo_dir: &Path, source: &str) -> Result<()> {
+        if source == self.config.repo_url {
+            return Ok(());
+        }
+        let args = vec![
+            "-C".to_string(),
+            repo_dir.display().to_string(),
+            "remote".to_string(),
+            "set-url".to_string(),
+            "origin".to_string(),
+            self.config.repo_url.clone(),
+        ];
+        run_command("git", &args, None)
+    }
+
+    /// ミラーを作成または更新する
+    pub fn fetch_mirror(&self) -> Result<()> {
+        self.ensure_online("ミラーの更新")?;
+        let mirror_dir = self.mirror_dir();
+        let mirror_str = mirror_dir.display().to_string();
+        if !mirror_dir.exists() {
+            let clone_args = vec![
+                "clone".to_string(),
+                "--mirror".to_string(),
+                self.config.repo_url.clone(),
+                mirror_str.clone(),
+            ];
+            run_command("git", &clone_args, None).inspect_err(|_err| {
+                if mirror_dir.exists() {
+                    let _ = fs::remove_dir_all(&mirror_dir);
+                }
+            })?;
+            // タスクの clone が alternates で参照するため、不要になったオブジェクトも消さない
+            let gc_args = vec![
+                "--git-dir".to_string(),
+                mirror_str,
+                "config".to_string(),
+                "gc.pruneExpire".to_string(),
+                "never".to_string(),
+            ];
+            run_command("git", &gc_args, None)?;
+            println!("ミラーを作成しました: {}", mirror_dir.display());
+            return Ok(());
+        }
+
+        let fetch_args = vec![
+            "--git-dir".to_string(),
+            mirror_str,
+            "remote".to_string(),
+            "update".to_string(),
+            "--prune".to_string(),
+        ];
+        run_command("git", &fetch_args, None)?;
+        println!("ミラーを更新しました: {}", mirror_dir.display());
+        Ok(())
+    }
+
+    /// auto_fetch が有効ならタスク作成前にミラーを更新する (失敗してもキャッシュで続行)
+    fn refresh_mirror_before_create(&self) {
+        if !self.config.auto_fetch || self.offline || !self.mirror_dir().exists() {
+            return;
+        }
+        if let Err(err) = self.fetch_mirror() {
+            eprintln!(
+                "警告: ミラーの更新に失敗したため、キャッシュ済みの内容で続行します: {:#}",
+                err
+            );
+        }
+    }
+
     pub fn task_exists(&self, task_name: &str) -> bool {
         self.workspaces_dir().join(task_name).exists()
     }
 
-    fn resolve_path(&self, relative: &str) -> PathBuf {
+    /// カレントディレクトリを含むワークスペースのタスク名
+    pub fn current_task(&self) -> Option<String> {
+        let cwd = env::current_dir().ok()?.canonicalize().ok()?;
+        let workspaces_dir = self.workspaces_dir().canonicalize().ok()?;
+        let name = cwd
+            .strip_prefix(&workspaces_dir)
+            .ok()?
+            .components()
+            .next()?;
+        let name = name.as_os_str().to_str()?.to_string();
+        self.task_exists(&name).then_some(name)
+    }
+
+    /// 引数のタスク名を解決する (`.` はカレントディレクトリのタスク)
+    pub fn resolve_task_name(&self, name: &str) -> Result<String> {
+        if name != "." {
+            return Ok(name.to_string());
+        }
+        match self.current_task() {
+            Some(name) => Ok(name),
+            None => bail!(
+                "カレントディレクトリはタスクのワークスペースではありません。タスク名を指定してください。"
+            ),
+        }
+    }
+
+    pub(crate) fn resolve_path(&self, relative: &str) -> PathBuf {
         let base = self
             .config_path
             .parent()
@@ -60,13 +242,16 @@ impl RepoManager {
             }
         }
 
+        self.fetch_mirror()?;
+
         let base_dir_str = base_dir.display().to_string();
+        let source = self.fetch_source()?;
         let clone_args = vec![
             "clone".to_string(),
             "--branch".to_string(),
             base_branch.to_string(),
             "--single-branch".to_string(),
-            self.config.repo_url.clone(),
+            source.clone(),
             base_dir_str.clone(),
         ];
         run_command("git", &clone_args, None).inspect_err(|_err| {
@@ -74,6 +259,7 @@ impl RepoManager {
                 let _ = fs::remove_dir_all(&base_dir);
             }
         })?;
+        self.point_origin_upstream(&base_dir, &source)?;
 
         let workspaces_dir = self.workspaces_dir();
         fs::create_dir_all(&workspaces_dir).with_context(|| {
@@ -86,6 +272,16 @@ impl RepoManager {
     }
 
     pub fn create_task_clone(&self, task_name: &str, base_branch: &str) -> Result<()> {
+        self.create_task_workspace(task_name, task_name, base_branch)
+    }
+
+    /// `branch` をチェックアウトしたワークスペースを `ws/<task_name>` に作成する
+    pub fn create_task_workspace(
+        &self,
+        task_name: &str,
+        branch: &str,
+        base_branch: &str,
+    ) -> Result<()> {
         let workspaces_dir = self.workspaces_dir();
         if !workspaces_dir.exists() {
             bail!(
@@ -103,93 +299,234 @@ impl RepoManager {
             );
         }
 
-        let repo_dir_str = workspace_dir.display().to_string();
-
-        let remote_branch_exists = self.remote_branch_exists(task_name)?;
-        let clone_branch = if remote_branch_exists {
-            task_name.to_string()
-        } else {
-            base_branch.to_string()
-        };
+        self.refresh_mirror_before_create();
+        let remote_branch_exists = self.remote_branch_exists(branch)?;
+        let backend = workspace::backend(self.config.workspace_strategy);
+        backend.create(
+            self,
+            &WorkspaceRequest {
+                workspace_dir: &workspace_dir,
+                branch,
+                base_branch,
+                remote_branch_exists,
+            },
+        )?;
 
-        let clone_args = vec![
-            "clone".to_string(),
-            "--branch".to_string(),
-            clone_branch.clone(),
-            "--single-branch".to_string(),
-            self.config.repo_url.clone(),
-            repo_dir_str.clone(),
+        let head_args = vec![
+            "-C".to_string(),
+            workspace_dir.display().to_string(),
+            "rev-parse".to_string(),
+            "HEAD".to_string(),
         ];
-        run_command("git", &clone_args, None).inspect_err(|_err| {
-            if workspace_dir.exists() {
-                let _ = fs::remove_dir_all(&workspace_dir);
-            }
-        })?;
+        let base_commit = run_command_quiet("git", &head_args, None).ok();
+        let mut state = self.load_state()?;
+        state.tasks.insert(
+            task_name.to_string(),
+            TaskRecord {
+                branch: branch.to_string(),
+                base_branch: base_branch.to_string(),
+                strategy: backend.strategy(),
+                created_at: state::now_timestamp(),
+                issue: None,
+                tool: None,
+                base_commit,
+            },
+        );
+        self.save_state(&state)?;
 
         if remote_branch_exists {
             println!(
-                "タスク \"{}\" 用のワークスペースをリモートブランチから取得しました: {}",
+                "タスク \"{}\" 用のワークスペースをリモートブランチから取得しました ({}): {}",
                 task_name,
+                backend.strategy(),
                 workspace_dir.display()
             );
         } else {
-            let branch_args = vec![
-                "-C".to_string(),
-                repo_dir_str.clone(),
-                "checkout".to_string(),
-                "-b".to_string(),
-                task_name.to_string(),
-            ];
-            run_command("git", &branch_args, None)?;
             println!(
-                "タスク \"{}\" 用のワークスペースとブランチ \"{}\" を作成しました: {}",
-                task_name,
+                "タスク \"{}\" 用のワークスペースとブランチ \"{}\" を作成しました ({}): {}",
                 task_name,
+                branch,
+                backend.strategy(),
                 workspace_dir.display()
             );
         }
         Ok(())
     }
 
-    pub fn remove_task_clone(&self, task_name: &str, force: bool) -> Result<()> {
+    pub fn remove_task_clone(&self, task_name: &str, options: &RemoveOptions) -> Result<()> {
         let workspace_dir = self.workspaces_dir().join(task_name);
         if !workspace_dir.exists() {
             bail!("タスク \"{}\" は存在しません。", task_name);
         }
 
-        if !force {
+        self.refresh_tracking_branch(&workspace_dir);
+        let inspection = match safety::inspect_workspace(&workspace_dir) {
+            Ok(inspection) => inspection,
+            Err(err) if options.force => {
+                eprintln!("警告: ワークスペースを検査できませんでした: {:#}", err);
+                WorkspaceInspection::default()
+            }
+            Err(err) => {
+                return Err(err.context(format!(
+                    "タスク \"{}\" のワークスペースを検査できませんでした。--force で強制的に削除できます。",
+                    task_name
+                )))
+            }
+        };
+        if !inspection.is_clean() && !options.force && !options.archive {
+            bail!(
+                "タスク \"{}\" には削除すると失われる作業があります。\n{}\
+                 --archive で退避してから削除するか、--force で強制的に削除してください。\n\
+                 (プッシュ済みの場合は 'aicloner fetch' でミラーを更新すると判定に反映されます)",
+                task_name,
+                inspection
+            );
+        }
+
+        if !options.yes {
+            if !inspection.is_clean() {
+                print!("{}", inspection);
+            }
             let prompt = format!(
                 "Remove workspace \"{}\" at \"{}\"? [y/N]: ",
                 task_name,
                 workspace_dir.display()
             );
-            print!("{prompt}");
-            io::stdout()
-                .flush()
-                .context("プロンプトの表示に失敗しました")?;
-            let mut answer = String::new();
-            io::stdin()
-                .read_line(&mut answer)
-                .context("入力の読み取りに失敗しました")?;
-            let answer = answer.trim().to_lowercase();
-            if answer != "y" {
+            if !confirm(&prompt)? {
                 println!("削除を中止しました。");
                 return Ok(());
             }
         }
 
-        fs::remove_dir_all(&workspace_dir).with_context(|| {
+        if options.archive && !inspection.is_clean() {
+            let archive_dir =
+                safety::archive_workspace(self, task_name, &workspace_dir, &inspection)?;
+            println!("作業内容を退避しました: {}", archive_dir.display());
+        }
+
+        workspace::backend(workspace::detect_strategy(&workspace_dir))
+            .remove(self, &workspace_dir)?;
+        let mut state = self.load_state()?;
+        if state.tasks.remove(task_name).is_some() {
+            self.save_state(&state)?;
+        }
+        println!(
+            "タスク \"{}\" のワークスペースを削除しました: {}",
+            task_name,
+            workspace_dir.display()
+        );
+        Ok(())
+    }
+
+    /// 現在のブランチのリモート追跡ブランチを取得元から更新する
+    /// clone は単一ブランチのため、プッシュしたブランチは追跡されず未プッシュと判定されてしまう
+    fn refresh_tracking_branch(&self, workspace_dir: &Path) {
+        let repo = workspace_dir.display().to_string();
+        let head_args = vec![
+            "-C".to_string(),
+            repo.clone(),
+            "symbolic-ref".to_string(),
+            "--short".to_string(),
+            "HEAD".to_string(),
+        ];
+        let (Ok(branch), Ok(source)) = (
+            run_command_quiet("git", &head_args, None),
+            self.fetch_source(),
+        ) else {
+            return;
+        };
+        let fetch_args = vec![
+            "-C".to_string(),
+            repo,
+            "fetch".to_string(),
+            "--quiet".to_string(),
+            source,
+            format!("+refs/heads/{0}:refs/remotes/origin/{0}", branch),
+        ];
+        // リモートにブランチが無い場合は失敗するが、その場合は更新するものが無い
+        let _ = run_command_quiet("git", &fetch_args, None);
+    }
+
+    /// タスクの既定のベースブランチ
+    /// 設定の `default_branch`、無ければ base でチェックアウトしているブランチ
+    pub fn default_branch(&self) -> Result<String> {
+        match &self.config.default_branch {
+            Some(branch) => Ok(branch.clone()),
+            None => self.current_base_branch(),
+        }
+    }
+
+    /// base で現在チェックアウトされているブランチ名
+    pub fn current_base_branch(&self) -> Result<String> {
+        let args = vec![
+            "-C".to_string(),
+            self.base_dir().display().to_string(),
+            "rev-parse".to_string(),
+            "--abbrev-ref".to_string(),
+            "HEAD".to_string(),
+        ];
+        run_command_capture("git", &args, None)
+            .context("base ディレクトリのブランチを取得できませんでした")
+    }
+
+    /// オブジェクト共有の参照先リポジトリ (共有しない場合は None)
+    pub fn reference_repo(&self) -> Result<Option<PathBuf>> {
+        if !self.config.share_objects {
+            return Ok(None);
+        }
+        // ミラーがあれば優先し、無ければ base を参照する
+        let reference_dir = [self.mirror_dir(), self.base_dir()]
+            .into_iter()
+            .find(|dir| dir.exists());
+        let Some(reference_dir) = reference_dir else {
+            return Ok(None);
+        };
+        let reference = std::path::absolute(&reference_dir).with_context(|| {
             format!(
-                "ディレクトリの削除に失敗しました: {}",
-                workspace_dir.display()
+                "参照リポジトリのパス解決に失敗しました: {}",
+                reference_dir.display()
             )
         })?;
+        Ok(Some(reference))
+    }
+
+    /// alternates で借りているオブジェクトを取り込み、ワークスペースを自己完結させる
+    /// 既に自己完結している場合は false を返す
+    pub fn repack_task(&self, task_name: &str) -> Result<bool> {
+        let workspace_dir = self.workspaces_dir().join(task_name);
+        if !workspace_dir.exists() {
+            bail!("タスク \"{}\" は存在しません。", task_name);
+        }
+        if workspace::detect_strategy(&workspace_dir) == WorkspaceStrategy::Worktree {
+            bail!(
+                "タスク \"{}\" は worktree のため repack できません。オブジェクトは base と共有されています。",
+                task_name
+            );
+        }
+
+        let alternates = workspace_dir.join(".git/objects/info/alternates");
+        if !alternates.exists() {
+            println!("タスク \"{}\" は既に自己完結しています。", task_name);
+            return Ok(false);
+        }
+
+        let repack_args = vec![
+            "-C".to_string(),
+            workspace_dir.display().to_string(),
+            "repack".to_string(),
+            "-a".to_string(),
+            "-d".to_string(),
+        ];
+        run_command("git", &repack_args, None)?;
+        fs::remove_file(&alternates).with_context(|| {
+            format!("alternates の削除に失敗しました: {}", alternates.display())
+        })?;
         println!(
-            "タスク \"{}\" のワークスペースを削除しました: {}",
+            "タスク \"{}\" のオブジェクトを取り込みました: {}",
             task_name,
             workspace_dir.display()
         );
-        Ok(())
+        Ok(true)
     }
 
     pub fn list_tasks(&self) -> Result<Vec<TaskInfo>> {
@@ -201,59 +538,46 @@ impl RepoManager {
             );
         }
         let mut tasks = Vec::new();
-        for entry in fs::read_dir(&workspaces_dir).with_context(|| {
-            format!(
-                "ワークスペース一覧の取得に失敗しました: {}",
-                workspaces_dir.display()
-            )
-        })? {
-            let entry = entry?;
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-            let name = entry.file_name().to_string_lossy().to_string();
-            let branch = if path.exists() {
-                let repo_str = path.display().to_string();
-                let args = vec![
-                    "-C".to_string(),
-                    repo_str.clone(),
-                    "rev-parse".to_string(),
-                    "--abbrev-ref".to_string(),
-                    "HEAD".to_string(),
-                ];
-                match run_command_capture("git", &args, None) {
-                    Ok(output) => Some(output.trim().to_string()),
-                    Err(_) => None,
-                }
-            } else {
-                None
-            };
-
-            tasks.push(TaskInfo {
-                name,
-                path: path.clone(),
-                branch,
-            });
+        for strategy in [WorkspaceStrategy::Clone, WorkspaceStrategy::Worktree] {
+            tasks.extend(workspace::backend(strategy).list(self)?);
+        }
+        let state = self.load_state()?;
+        for task in &mut tasks {
+            task.record = state.tasks.get(&task.name).cloned();
         }
         tasks.sort_by(|a, b| a.name.cmp(&b.name));
         Ok(tasks)
     }
 }
 
+/// rm の実行オプション
+#[derive(Debug, Clone, Default)]
+pub struct RemoveOptions {
+    /// 失われる作業があっても削除する
+    pub force: bool,
+    /// 失われる作業を `.aicloner/trash/` に退避してから削除する
+    pub archive: bool,
+    /// 確認プロンプトを出さない
+    pub yes: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct TaskInfo {
     pub name: String,
     pub path: PathBuf,
     pub branch: Option<String>,
+    pub strategy: WorkspaceStrategy,
+    /// `.aicloner/state.toml` に記録されたメタデータ
+    pub record: Option<TaskRecord>,
 }
 
 impl RepoManager {
-    fn remote_branch_exists(&self, branch: &str) -> Result<bool> {
+    pub(crate) fn remote_branch_exists(&self, branch: &str) -> Result<bool> {
         let pattern = format!("refs/heads/{}", branch);
         let args = vec![
             "ls-remote".to_string(),
             "--heads".to_string(),
-            self.config.repo_url.clone(),
+            self.fetch_source()?,
             pattern,
         ];
         let output = run_command_capture("git", &args, None)?;
@@ -261,7 +585,45 @@ impl RepoManager {
     }
 }
 
-fn run_command(program: &str, args: &[String], dir: Option<&Path>) -> Result<()> {
+/// リモートの既定ブランチを調べる
+/// `git ls-remote --symref` で HEAD の参照先を読み、分からなければ main、master の順に存在するものを使う
+pub fn detect_default_branch(source: &str) -> Result<String> {
+    let args = vec![
+        "ls-remote".to_string(),
+        "--symref".to_string(),
+        source.to_string(),
+        "HEAD".to_string(),
+    ];
+    let output = run_command_capture("git", &args, None)?;
+    // `ref: refs/heads/<branch>\tHEAD`
+    let symref = output.lines().find_map(|line| {
+        line.strip_prefix("ref: refs/heads/")
+            .and_then(|rest| rest.split('\t').next())
+    });
+    if let Some(branch) = symref {
+        return Ok(branch.to_string());
+    }
+
+    let args = vec![
+        "ls-remote".to_string(),
+        "--heads".to_string(),
+        source.to_string(),
+    ];
+    let heads = run_command_capture("git", &args, None)?;
+    for candidate in ["main", "master"] {
+        let head_ref = format!("refs/heads/{}", candidate);
+        if heads.lines().any(|line| line.ends_with(&head_ref)) {
+            return Ok(candidate.to_string());
+        }
+    }
+    bail!(
+        "リモートの既定ブランチを判定できませんでした: {}\n\
+         --branch でブランチを指定してください。",
+        source
+    )
+}
+
+pub(crate) fn run_command(program: &str, args: &[String], dir: Option<&Path>) -> Result<()> {
     log_command(program, args, dir);
     let mut command = Command::new(program);
     if let Some(dir) = dir {
@@ -284,8 +646,21 @@ fn run_command(program: &str, args: &[String], dir: Option<&Path>) -> Result<()>
     Ok(())
 }
 
-fn run_command_capture(program: &str, args: &[String], dir: Option<&Path>) -> Result<String> {
+pub(crate) fn run_command_capture(
+    program: &str,
+    args: &[String],
+    dir: Option<&Path>,
+) -> Result<String> {
     log_command(program, args, dir);
+    run_command_quiet(program, args, dir)
+}
+
+/// ログを出さずに実行して標準出力を返す (並列で行う読み取り専用の問い合わせ用)
+pub(crate) fn run_command_quiet(
+    program: &str,
+    args: &[String],
+    dir: Option<&Path>,
+) -> Result<String> {
     let mut command = Command::new(program);
     if let Some(dir) = dir {
         command.current_dir(dir);
@@ -307,11 +682,31 @@ fn run_command_capture(program: &str, args: &[String], dir: Option<&Path>) -> Re
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// `[y/N]` の確認プロンプトを表示し、`y` が入力されたかを返す
+/// 標準入力が端末でなければ入力を待たずにエラーにする
+pub(crate) fn confirm(prompt: &str) -> Result<bool> {
+    if !io::stdin().is_terminal() {
+        bail!(
+            "確認が必要ですが、標準入力が端末ではありません。\n\
+             --yes (--non-interactive) を付けて実行してください。"
+        );
+    }
+    print!("{prompt}");
+    io::stdout()
+        .flush()
+        .context("プロンプトの表示に失敗しました")?;
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("入力の読み取りに失敗しました")?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
 fn log_command(program: &str, args: &[String], dir: Option<&Path>) {
     let joined = args.join(" ");
     match dir {
-        Some(d) => println!("実行: {} {} (cwd: {})", program, joined, d.display()),
-        None => println!("実行: {} {}", program, joined),
+        Some(d) => eprintln!("実行: {} {} (cwd: {})", program, joined, d.display()),
+        None => eprintln!("実行: {} {}", program, joined),
     }
 }
 
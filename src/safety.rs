@@ -0,0 +1,212 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    config::WorkspaceStrategy,
+    repo::{run_command_quiet, RepoManager},
+    state, workspace,
+};
+
+/// 削除すると失われる作業の一覧
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkspaceInspection {
+    /// `git status --porcelain` の各行 (未追跡ファイルを含む)
+    pub uncommitted: Vec<String>,
+    /// `git stash list` の各行
+    pub stashes: Vec<String>,
+    /// どのリモートブランチにも含まれないコミット (`<ref>: <hash> <subject>`)
+    pub unpushed: Vec<String>,
+    /// 未プッシュのコミットを持つ ref
+    pub unpushed_refs: Vec<String>,
+}
+
+impl WorkspaceInspection {
+    pub fn is_clean(&self) -> bool {
+        self.uncommitted.is_empty() && self.stashes.is_empty() && self.unpushed.is_empty()
+    }
+}
+
+impl fmt::Display for WorkspaceInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            ("未コミットの変更", &self.uncommitted),
+            ("stash", &self.stashes),
+            ("未プッシュのコミット", &self.unpushed),
+        ];
+        for (label, lines) in sections {
+            if lines.is_empty() {
+                continue;
+            }
+            writeln!(f, "{} ({} 件):", label, lines.len())?;
+            for line in lines {
+                writeln!(f, "  {}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// ワークスペースを削除した場合に失われる作業を調べる
+pub fn inspect_workspace(workspace_dir: &Path) -> Result<WorkspaceInspection> {
+    let mut inspection = WorkspaceInspection {
+        uncommitted: non_empty_lines(&git(
+            workspace_dir,
+            &["status", "--porcelain", "--untracked-files=all"],
+        )?),
+        ..Default::default()
+    };
+
+    // worktree の stash やブランチは base と共有しており、削除しても失われない
+    let strategy = workspace::detect_strategy(workspace_dir);
+    if strategy == WorkspaceStrategy::Clone {
+        inspection.stashes = non_empty_lines(&git(workspace_dir, &["stash", "list"])?);
+    }
+
+    for reference in refs_to_check(workspace_dir, strategy)? {
+        let log = git(
+            workspace_dir,
+            &["log", "--format=%h %s", &reference, "--not", "--remotes"],
+        )?;
+        let commits = non_empty_lines(&log);
+        if commits.is_empty() {
+            continue;
+        }
+        inspection.unpushed.extend(
+            commits
+                .iter()
+                .map(|commit| format!("{}: {}", reference, commit)),
+        );
+        inspection.unpushed_refs.push(reference);
+    }
+    Ok(inspection)
+}
+
+/// 未プッシュのコミットを探す ref
+/// clone では全ローカルブランチ、worktree では現在のブランチのみを対象にする
+/// (HEAD が detached の場合は HEAD も含める)
+fn refs_to_check(workspace_dir: &Path, strategy: WorkspaceStrategy) -> Result<Vec<String>> {
+    let head = git(workspace_dir, &["symbolic-ref", "--quiet", "HEAD"]).ok();
+    let mut refs = match (strategy, &head) {
+        (WorkspaceStrategy::Clone, _) => non_empty_lines(&git(
+            workspace_dir,
+            &["for-each-ref", "--format=%(refname)", "refs/heads"],
+        )?),
+        (WorkspaceStrategy::Worktree, Some(head)) => vec![head.clone()],
+        (WorkspaceStrategy::Worktree, None) => Vec::new(),
+    };
+    if head.is_none() && git(workspace_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok() {
+        refs.push("HEAD".to_string());
+    }
+    Ok(refs)
+}
+
+/// 失われる作業を `.aicloner/trash/<task>-<日時>/` に退避し、退避先を返す
+///
+/// - `unpushed.bundle`: 未プッシュの ref の `git bundle`
+/// - `uncommitted.patch`: HEAD からの未コミットの差分
+/// - `stash-<n>.patch`: 各 stash の差分
+/// - `untracked/`: 未追跡ファイルのコピー
+pub fn archive_workspace(
+    manager: &RepoManager,
+    task_name: &str,
+    workspace_dir: &Path,
+    inspection: &WorkspaceInspection,
+) -> Result<PathBuf> {
+    let stamp: String = state::now_timestamp()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let archive_dir = manager.trash_dir().join(format!("{}-{}", task_name, stamp));
+    fs::create_dir_all(&archive_dir).with_context(|| {
+        format!(
+            "退避先ディレクトリの作成に失敗しました: {}",
+            archive_dir.display()
+        )
+    })?;
+
+    if !inspection.unpushed_refs.is_empty() {
+        let bundle = archive_dir.join("unpushed.bundle");
+        let mut args = vec![
+            "bundle".to_string(),
+            "create".to_string(),
+            bundle.display().to_string(),
+        ];
+        args.extend(inspection.unpushed_refs.iter().cloned());
+        args.extend(["--not".to_string(), "--remotes".to_string()]);
+        git_owned(workspace_dir, args)?;
+    }
+
+    let has_head = git(workspace_dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok();
+    if has_head
+        && inspection
+            .uncommitted
+            .iter()
+            .any(|line| !line.starts_with("??"))
+    {
+        let patch = archive_dir.join("uncommitted.patch");
+        git_owned(
+            workspace_dir,
+            vec![
+                "diff".to_string(),
+                "--binary".to_string(),
+                format!("--output={}", patch.display()),
+                "HEAD".to_string(),
+            ],
+        )?;
+    }
+
+    for index in 0..inspection.stashes.len() {
+        let patch = archive_dir.join(format!("stash-{}.patch", index));
+        git_owned(
+            workspace_dir,
+            vec![
+                "stash".to_string(),
+                "show".to_string(),
+                "--patch".to_string(),
+                "--binary".to_string(),
+                format!("--output={}", patch.display()),
+                format!("stash@{{{}}}", index),
+            ],
+        )?;
+    }
+
+    let untracked = git(
+        workspace_dir,
+        &["ls-files", "--others", "--exclude-standard", "-z"],
+    )?;
+    for file in untracked.split('\0').filter(|file| !file.is_empty()) {
+        let dest = archive_dir.join("untracked").join(file);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(workspace_dir.join(file), &dest)
+            .with_context(|| format!("未追跡ファイルの退避に失敗しました: {}", file))?;
+    }
+
+    Ok(archive_dir)
+}
+
+fn git(workspace_dir: &Path, args: &[&str]) -> Result<String> {
+    git_owned(
+        workspace_dir,
+        args.iter().map(|arg| arg.to_string()).collect(),
+    )
+}
+
+fn git_owned(workspace_dir: &Path, args: Vec<String>) -> Result<String> {
+    let mut full = vec!["-C".to_string(), workspace_dir.display().to_string()];
+    full.extend(args);
+    run_command_quiet("git", &full, None)
+}
+
+fn non_empty_lines(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,331 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{repo::RepoManager, state};
+
+/// ヘッダとフッタの行の接頭辞
+const META_PREFIX: &str = "# ";
+/// `logs --follow` で追記を確認する間隔
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// `.aicloner/logs/<task>/<時刻>.log` に書き込むセッションのログ
+///
+/// 先頭に task / tool / pid / started、終了時に finished / exit の行を `# key: value` の形で書く。
+/// `finish` を呼ばずに破棄されたときは `# exit: interrupted` を書く。
+pub struct SessionLog {
+    pub path: PathBuf,
+    file: File,
+    /// 記録した出力が行の途中で終わっているか
+    mid_line: bool,
+    /// 終了の行を書き込んだか
+    finished: bool,
+}
+
+impl SessionLog {
+    /// ログファイルを作成してヘッダを書き込む
+    pub fn create(manager: &RepoManager, task_name: &str, tool_name: &str) -> Result<Self> {
+        let dir = manager.logs_dir().join(task_name);
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("ログディレクトリの作成に失敗しました: {}", dir.display()))?;
+        let started = state::now_timestamp();
+        let stamp: String = started
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect();
+
+        let mut suffix = 1;
+        let (path, file) = loop {
+            let name = match suffix {
+                1 => format!("{}.log", stamp),
+                n => format!("{}-{}.log", stamp, n),
+            };
+            let path = dir.join(name);
+            match OpenOptions::new().append(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => suffix += 1,
+                Err(err) => {
+                    return Err(err).with_context(|| {
+                        format!("ログファイルの作成に失敗しました: {}", path.display())
+                    })
+                }
+            }
+        };
+
+        let mut log = Self {
+            path,
+            file,
+            mid_line: false,
+            finished: false,
+        };
+        log.write_meta("task", task_name)?;
+        log.write_meta("tool", tool_name)?;
+        // 書き込むプロセスが終了コードを書けずに落ちても `logs --follow` が止まれるように残す
+        log.write_meta("pid", &std::process::id().to_string())?;
+        log.write_meta("started", &started)?;
+        Ok(log)
+    }
+
+    /// 子プロセスの出力先にするためのファイル
+    pub fn output_file(&self) -> Result<File> {
+        self.file
+            .try_clone()
+            .context("ログファイルを開けませんでした")
+    }
+
+    /// 端末の出力を記録する (各行の先頭に `[HH:MM:SS]` を付ける)
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub fn write_transcript(&mut self, bytes: &[u8]) -> Result<()> {
+        let mut buffer = Vec::with_capacity(bytes.len() + 16);
+        for line in bytes.split_inclusive(|&byte| byte == b'\n') {
+            if !self.mid_line {
+                let now = state::now_timestamp();
+                let time = now.get(11..19).unwrap_or(&now);
+                buffer.extend_from_slice(format!("[{}] ", time).as_bytes());
+            }
+            buffer.extend_from_slice(line);
+            self.mid_line = !line.ends_with(b"\n");
+        }
+        self.write(&buffer)
+    }
+
+    /// 終了時刻と終了コードを書き込む
+    pub fn finish(&mut self, status: Option<ExitStatus>) -> Result<()> {
+        let exit = match status.map(|status| status.code()) {
+            Some(Some(code)) => code.to_string(),
+            Some(None) => "signal".to_string(),
+            None => "unknown".to_string(),
+        };
+        self.write_footer(&exit)
+    }
+
+    fn write_footer(&mut self, exit: &str) -> Result<()> {
+        self.finished = true;
+        if self.mid_line {
+            self.write(b"\n")?;
+            self.mid_line = false;
+        }
+        self.write_meta("finished", &state::now_timestamp())?;
+        self.write_meta("exit", exit)
+    }
+
+    fn write_meta(&mut self, key: &str, value: &str) -> Result<()> {
+        let line = format!("{}{}: {}\n", META_PREFIX, key, value);
+        self.write(line.as_bytes())
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        self.file
+            .write_all(bytes)
+            .with_context(|| format!("ログの書き込みに失敗しました: {}", self.path.display()))
+    }
+}
+
+impl Drop for SessionLog {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.write_footer("interrupted");
+        }
+    }
+}
+
+/// 保存済みのログ 1 件
+pub struct LogEntry {
+    pub path: PathBuf,
+    pub started: Option<String>,
+    /// 終了コード (実行中または中断されたときは None)
+    pub exit: Option<String>,
+    pub size: u64,
+}
+
+/// タスクのログを古い順に返す
+pub fn list_logs(manager: &RepoManager, task_name: &str) -> Result<Vec<LogEntry>> {
+    let dir = manager.logs_dir().join(task_name);
+    if !dir.exists() {
+        bail!("タスク \"{}\" のログがありません", task_name);
+    }
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&dir)
+        .with_context(|| format!("ログディレクトリを読み込めません: {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("log") {
+            paths.push(path);
+        }
+    }
+    paths.sort_by_key(|path| sort_key(path));
+
+    let mut entries = Vec::new();
+    for path in paths {
+        let content = fs::read(&path)
+            .with_context(|| format!("{} の読み込みに失敗しました", path.display()))?;
+        let content = String::from_utf8_lossy(&content);
+        entries.push(LogEntry {
+            started: meta_value(&content, "started"),
+            exit: meta_value(&content, "exit"),
+            size: content.len() as u64,
+            path,
+        });
+    }
+    if entries.is_empty() {
+        bail!("タスク \"{}\" のログがありません", task_name);
+    }
+    Ok(entries)
+}
+
+/// ログを標準出力に書き出す
+/// `follow` のときは終了コードの行が書き込まれるか、書き込むプロセスが無くなるまで追記を待って出力し続ける
+pub fn show_log(path: &Path, follow: bool) -> Result<()> {
+    let mut file =
+        File::open(path).with_context(|| format!("{} を開けませんでした", path.display()))?;
+    let mut stdout = io::stdout();
+    let mut tail = String::new();
+    let mut writer = None;
+    let mut writer_gone = false;
+    loop {
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)
+            .with_context(|| format!("{} の読み込みに失敗しました", path.display()))?;
+        stdout.write_all(&buf)?;
+        stdout.flush()?;
+        if !follow || writer_gone {
+            return Ok(());
+        }
+
+        // 直近の数行だけを見て終了を判定する
+        tail.push_str(&String::from_utf8_lossy(&buf));
+        if writer.is_none() {
+            writer = meta_value(&tail, "pid").and_then(|pid| pid.parse::<u32>().ok());
+        }
+        if tail.len() > 4096 {
+            let start = tail.len() - 4096;
+            let start = (start..tail.len())
+                .find(|&i| tail.is_char_boundary(i))
+                .unwrap_or(0);
+            tail.drain(..start);
+        }
+        if meta_value(&tail, "exit").is_some() {
+            return Ok(());
+        }
+        // 終了コードを書けずに落ちた場合は、最後の追記を読んでから終える
+        if writer.is_some_and(|pid| !process_exists(pid)) {
+            writer_gone = true;
+            continue;
+        }
+        thread::sleep(FOLLOW_INTERVAL);
+    }
+}
+
+/// `pid` のプロセスが動いているか
+#[cfg(unix)]
+fn process_exists(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // シグナル 0 は何も送らずに存在だけを確かめる (EPERM は権限が無いだけで存在している)
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// `pid` のプロセスが動いているか
+#[cfg(not(unix))]
+fn process_exists(pid: u32) -> bool {
+    let filter = format!("PID eq {}", pid);
+    std::process::Command::new("tasklist")
+        .args(["/FI", &filter, "/NH", "/FO", "CSV"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", pid)))
+        // 確かめられなければ動いているものとして待ち続ける
+        .unwrap_or(true)
+}
+
+/// `<時刻>.log` / `<時刻>-<n>.log` を作成順に並べるためのキー
+fn sort_key(path: &Path) -> (String, u32) {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default();
+    match stem.split_once('-') {
+        Some((stamp, n)) => (stamp.to_string(), n.parse().unwrap_or(0)),
+        None => (stem.to_string(), 1),
+    }
+}
+
+/// ログ中の `# key: value` の値 (出力に同じ形の行があっても最後のものを使う)
+fn meta_value(content: &str, key: &str) -> Option<String> {
+    let prefix = format!("{}{}: ", META_PREFIX, key);
+    content
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(&prefix))
+        .map(|value| value.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_value_and_sort_key() {
+        let content = "# task: t\n# started: 2024-05-01T10:00:00Z\n[10:00:01] hi\n# exit: 0\n";
+        assert_eq!(
+            meta_value(content, "started").as_deref(),
+            Some("2024-05-01T10:00:00Z")
+        );
+        assert_eq!(meta_value(content, "exit").as_deref(), Some("0"));
+        assert_eq!(meta_value(content, "finished"), None);
+
+        let mut paths = [
+            PathBuf::from("20240501T100000Z-10.log"),
+            PathBuf::from("20240501T100000Z-2.log"),
+            PathBuf::from("20240501T100000Z.log"),
+        ];
+        paths.sort_by_key(|path| sort_key(path));
+        assert_eq!(paths[0], PathBuf::from("20240501T100000Z.log"));
+        assert_eq!(paths[2], PathBuf::from("20240501T100000Z-10.log"));
+    }
+
+    #[test]
+    fn test_drop_without_finish_marks_interrupted() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let manager = RepoManager::new(
+            crate::config::Config::default(),
+            tmp.path().join(".aicloner.toml"),
+        );
+        let log = SessionLog::create(&manager, "task", "stub").unwrap();
+        let path = log.path.clone();
+        drop(log);
+
+        let content = fs::read_to_string(&path).unwrap();
+        let pid = std::process::id().to_string();
+        assert_eq!(meta_value(&content, "pid"), Some(pid));
+        assert_eq!(meta_value(&content, "exit").as_deref(), Some("interrupted"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_stops_when_writer_is_gone() {
+        let mut writer = std::process::Command::new("true").spawn().unwrap();
+        let pid = writer.id();
+        writer.wait().unwrap();
+        assert!(!process_exists(pid));
+        assert!(process_exists(std::process::id()));
+
+        // 終了コードの行が無くても、書き込むプロセスが無ければ待ち続けない
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("20240501T100000Z.log");
+        fs::write(
+            &path,
+            format!("# task: t\n# pid: {}\n# started: x\n[10:00:01] hi\n", pid),
+        )
+        .unwrap();
+        show_log(&path, true).unwrap();
+    }
+}
@@ -1,224 +1,624 @@
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-use anyhow::{bail, Context, Result};
-
-use crate::ai_tool::AiTool;
-use crate::repo::RepoManager;
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+
+use crate::ai_tool::{AiTool, SessionInput};
+use crate::branch::{self, BranchVars};
+use crate::forge;
+use crate::issue::Issue;
+use crate::prompt::{self, PromptContext, RESUME_TEMPLATE};
+use crate::repo::{run_command_quiet, RepoManager, TaskInfo};
+#[cfg(unix)]
+use crate::session_log::SessionLog;
+
+/// start の実行オプション
+pub struct StartOptions {
+    pub plan: bool,
+    /// 使用するプロンプトテンプレート名
+    pub template: String,
+    /// 擬似端末の上で起動し、セッションを `.aicloner/logs/` に記録する
+    pub record: bool,
+    /// ワークスペースのベースにするブランチ (省略時は設定の `default_branch`)
+    pub base_branch: Option<String>,
+    /// 作成するブランチ名 (省略時は設定の `branch_template` から作る)
+    pub branch: Option<String>,
+    /// 既存のブランチと衝突したときの扱い (省略時は対話で選ぶ)
+    pub on_conflict: Option<ConflictPolicy>,
+    /// 標準入力から選択や入力を読めるか
+    pub interactive: bool,
+}
 
-pub fn handle_start(issue_number: u32, ai_tool: AiTool, manager: RepoManager, plan: bool) -> Result<()> {
-    // Issue existence verification
-    verify_issue_exists(issue_number, &manager)?;
+/// 既存のブランチと衝突したときの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// 既存のワークスペースでセッションを再開する
+    Reuse,
+    /// 新しいブランチ名を入力して作成する
+    New,
+    /// 何もせずに中止する
+    Abort,
+    /// `-2`, `-3`, … を付けた空いている名前で作成する
+    Suffix,
+}
 
-    // Determine base branch
-    let base_branch = determine_base_branch(&manager)?;
+/// ワークスペースを作成し、AI ツールを起動する準備ができたタスク
+pub struct PreparedTask {
+    pub task_name: String,
+    pub workspace_path: PathBuf,
+    pub prompt: String,
+    pub prompt_file: PathBuf,
+}
 
-    // Generate branch name
-    let mut branch_name = format!("aicloner-issue{}", issue_number);
+pub fn handle_start(
+    issue_number: u32,
+    ai_tool: AiTool,
+    manager: RepoManager,
+    options: &StartOptions,
+) -> Result<()> {
+    // Issue existence verification
+    let issue = fetch_issue(issue_number, &manager)?;
 
     // Check for conflicts and resolve
-    if manager.task_exists(&branch_name) {
-        branch_name = handle_branch_conflict(&branch_name, issue_number)?;
+    let mut branch_name = match &options.branch {
+        Some(branch) => branch.clone(),
+        None => issue_branch_name(&manager, issue_number, issue.as_ref(), &ai_tool)?,
+    };
+    branch::check_ref_format(&branch_name)?;
+    let task_name = branch::task_name(&branch_name);
+    if manager.task_exists(&task_name) {
+        match resolve_conflict(&manager, &branch_name, options)? {
+            ConflictResolution::Reuse => {
+                let resume = ResumeOptions {
+                    plan: options.plan,
+                    template: RESUME_TEMPLATE.to_string(),
+                    record: options.record,
+                    prompt: false,
+                };
+                return handle_resume(&task_name, &ai_tool, &manager, &resume);
+            }
+            ConflictResolution::Rename(name) => {
+                branch::check_ref_format(&name)?;
+                branch_name = name;
+            }
+        }
     }
 
-    // Create workspace
-    let workspace_path = create_workspace_for_issue(&manager, &branch_name, &base_branch)?;
+    let task = prepare_task(
+        issue_number,
+        issue.as_ref(),
+        &branch_name,
+        &ai_tool,
+        &manager,
+        options,
+    )?;
 
     // Launch AI tool session
-    launch_ai_session(&workspace_path, issue_number, ai_tool, plan)?;
+    let input = SessionInput::Prompt {
+        text: &task.prompt,
+        file: Some(&task.prompt_file),
+    };
+    launch_ai_session(
+        &manager,
+        &task.task_name,
+        &task.workspace_path,
+        input,
+        &ai_tool,
+        options,
+    )?;
 
     Ok(())
 }
 
-fn verify_issue_exists(issue_number: u32, manager: &RepoManager) -> Result<()> {
-    let base_dir = manager.base_dir();
+/// `branch_name` のワークスペースを作成し、プロンプトを描画する
+/// 同名のタスクが既にあればエラーにする
+pub fn prepare_task(
+    issue_number: u32,
+    issue: Option<&Issue>,
+    branch_name: &str,
+    ai_tool: &AiTool,
+    manager: &RepoManager,
+    options: &StartOptions,
+) -> Result<PreparedTask> {
+    let task_name = branch::task_name(branch_name);
+    if manager.task_exists(&task_name) {
+        bail!("ブランチ \"{}\" は既に存在します", branch_name);
+    }
 
-    let args = vec![
-        "issue".to_string(),
-        "view".to_string(),
-        issue_number.to_string(),
-    ];
+    // Determine base branch
+    let base_branch = determine_base_branch(manager, options)?;
+
+    // Render the prompt before creating the workspace so template errors leave nothing behind
+    let context = prompt_context(
+        manager,
+        issue_number,
+        issue,
+        branch_name,
+        &base_branch,
+        ai_tool,
+        options.plan,
+    );
+    let prompt = prompt::render_prompt(manager, &options.template, &context)?;
 
-    println!("実行: gh {} (cwd: {})", args.join(" "), base_dir.display());
-    let output = Command::new("gh")
-        .args(&args)
-        .current_dir(&base_dir)
-        .output()
-        .context("gh issue view の実行に失敗しました")?;
+    // Create workspace
+    let workspace_path =
+        create_workspace_for_issue(manager, &task_name, branch_name, &base_branch)?;
+    manager.update_task_record(&task_name, |record| {
+        record.issue = Some(issue_number);
+        record.tool = Some(ai_tool.name.clone());
+    })?;
+
+    Ok(PreparedTask {
+        workspace_path,
+        prompt,
+        prompt_file: prompt_file_path(manager, &task_name, "prompt")?,
+        task_name,
+    })
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        bail!("Issue #{} が見つかりません: {}", issue_number, stderr.trim());
+/// resume の実行オプション
+pub struct ResumeOptions {
+    pub plan: bool,
+    /// `resume_args` の無いツールに渡すプロンプトのテンプレート名
+    pub template: String,
+    /// 擬似端末の上で起動し、セッションを `.aicloner/logs/` に記録する
+    pub record: bool,
+    /// `resume_args` があってもテンプレートのプロンプトで新しいセッションを始める
+    pub prompt: bool,
+}
+
+/// 既存のワークスペースで AI ツールを起動し直す
+///
+/// ツールに `resume_args` があれば前回のセッションを再開し、
+/// 無ければ現在の `git log` / `git status` をまとめたプロンプトを渡す。
+pub fn handle_resume(
+    task_name: &str,
+    ai_tool: &AiTool,
+    manager: &RepoManager,
+    options: &ResumeOptions,
+) -> Result<()> {
+    if !manager.task_exists(task_name) {
+        bail!("タスク \"{}\" が見つかりません", task_name);
     }
+    let workspace_path = manager.workspaces_dir().join(task_name);
+    let start_options = StartOptions {
+        plan: options.plan,
+        template: options.template.clone(),
+        record: options.record,
+        base_branch: None,
+        branch: None,
+        on_conflict: None,
+        interactive: false,
+    };
 
-    println!("✓ Issue #{} を確認しました", issue_number);
-    Ok(())
+    if ai_tool.spec.resume_args.is_some() && !options.prompt {
+        println!("✓ 前回のセッションを再開します");
+        return launch_ai_session(
+            manager,
+            task_name,
+            &workspace_path,
+            SessionInput::Resume,
+            ai_tool,
+            &start_options,
+        );
+    }
+
+    let prompt = render_resume_prompt(task_name, &workspace_path, ai_tool, manager, options)?;
+    let prompt_file = prompt_file_path(manager, task_name, "resume")?;
+    let input = SessionInput::Prompt {
+        text: &prompt,
+        file: Some(&prompt_file),
+    };
+    launch_ai_session(
+        manager,
+        task_name,
+        &workspace_path,
+        input,
+        ai_tool,
+        &start_options,
+    )
+}
+
+/// `resume` の対象をタスクにする
+/// タスク名のほか、`12` / `#12` のような Issue 番号も受け付ける
+pub fn resolve_task(manager: &RepoManager, target: &str) -> Result<TaskInfo> {
+    let tasks = manager.list_tasks()?;
+    if let Some(task) = tasks.iter().find(|task| task.name == target) {
+        return Ok(task.clone());
+    }
+    let Ok(issue_number) = target.trim_start_matches('#').parse::<u32>() else {
+        bail!("タスク \"{}\" が見つかりません", target);
+    };
+    let names = issue_workspaces(&tasks, issue_number, &manager.config.branch_template);
+    match names.as_slice() {
+        [] => bail!("Issue #{} のワークスペースがありません", issue_number),
+        [name] => Ok(tasks
+            .into_iter()
+            .find(|task| &task.name == name)
+            .expect("issue_workspaces は一覧のタスク名を返す")),
+        _ => bail!(
+            "Issue #{} のワークスペースが複数あります。タスク名を指定してください: {}",
+            issue_number,
+            names.join(", ")
+        ),
+    }
+}
+
+/// `resume` が送るプロンプトを描画する
+fn render_resume_prompt(
+    task_name: &str,
+    workspace_path: &Path,
+    ai_tool: &AiTool,
+    manager: &RepoManager,
+    options: &ResumeOptions,
+) -> Result<String> {
+    let record = manager.load_state()?.tasks.get(task_name).cloned();
+    let issue_number = record.as_ref().and_then(|record| record.issue);
+    let issue = match issue_number {
+        // Issue を取得できなくても作業の再開は妨げない
+        Some(number) => fetch_issue(number, manager).unwrap_or_else(|err| {
+            eprintln!("警告: Issue #{} を取得できませんでした: {:#}", number, err);
+            None
+        }),
+        None => None,
+    };
+    let base_branch = match &record {
+        Some(record) => record.base_branch.clone(),
+        None => manager.default_branch()?,
+    };
+    let git = |args: &[&str]| {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        run_command_quiet("git", &args, Some(workspace_path))
+    };
+    let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])
+        .unwrap_or_else(|_| task_name.to_string());
+
+    // 作成時のコミットが分かればそこから、無ければベースブランチからのコミットを並べる
+    let since = match record.as_ref().and_then(|record| record.base_commit.clone()) {
+        Some(commit) => commit,
+        None => format!("origin/{}", base_branch),
+    };
+    let range = format!("{}..HEAD", since);
+    let git_log = git(&["log", "--oneline", "--no-decorate", "-n", "50", &range])
+        .or_else(|_| git(&["log", "--oneline", "--no-decorate", "-n", "10"]))?;
+    let git_status = git(&["status", "--short", "--branch"])?;
+
+    let mut context = prompt_context(
+        manager,
+        issue_number.unwrap_or_default(),
+        issue.as_ref(),
+        &branch,
+        &base_branch,
+        ai_tool,
+        options.plan,
+    );
+    context.git_log = git_log;
+    // `## <branch>` だけなら未コミットの変更は無い
+    context.git_status = if git_status.lines().count() > 1 {
+        git_status
+    } else {
+        String::new()
+    };
+    prompt::render_prompt(manager, &options.template, &context)
 }
 
-fn determine_base_branch(manager: &RepoManager) -> Result<String> {
-    let base_dir = manager.base_dir();
+/// `.aicloner/tmp/<task>-<kind>.md`
+fn prompt_file_path(manager: &RepoManager, task_name: &str, kind: &str) -> Result<PathBuf> {
+    Ok(std::path::absolute(
+        manager
+            .state_dir()
+            .join("tmp")
+            .join(format!("{}-{}.md", task_name, kind)),
+    )?)
+}
 
-    let args = vec![
-        "-C".to_string(),
-        base_dir.display().to_string(),
-        "rev-parse".to_string(),
-        "--abbrev-ref".to_string(),
-        "HEAD".to_string(),
-    ];
+/// `start` が送るプロンプトを描画する (ワークスペースは作成しない)
+pub fn render_start_prompt(
+    issue_number: u32,
+    ai_tool: &AiTool,
+    manager: &RepoManager,
+    options: &StartOptions,
+) -> Result<String> {
+    let issue = fetch_issue(issue_number, manager)?;
+    let base_branch = select_base_branch(manager, options)?;
+    let branch_name = issue_branch_name(manager, issue_number, issue.as_ref(), ai_tool)?;
+    let context = prompt_context(
+        manager,
+        issue_number,
+        issue.as_ref(),
+        &branch_name,
+        &base_branch,
+        ai_tool,
+        options.plan,
+    );
+    prompt::render_prompt(manager, &options.template, &context)
+}
 
-    println!("実行: git {}", args.join(" "));
-    let output = Command::new("git")
-        .args(&args)
-        .output()
-        .context("base ディレクトリのブランチ取得に失敗しました")?;
+/// 設定の `branch_template` から Issue のブランチ名を作る
+pub fn issue_branch_name(
+    manager: &RepoManager,
+    issue_number: u32,
+    issue: Option<&Issue>,
+    ai_tool: &AiTool,
+) -> Result<String> {
+    let vars = BranchVars {
+        issue_number,
+        issue_title: issue.map_or("", |issue| issue.title.as_str()),
+        tool: &ai_tool.name,
+    };
+    branch::render(&manager.config.branch_template, &vars)
+}
+
+/// Issue に対応するワークスペース
+/// 状態ファイルの Issue 番号で探し、記録が無ければ `branch_template` の形 (`-2` などの接尾辞付きを含む) で判断する
+pub fn issue_workspaces(
+    tasks: &[TaskInfo],
+    issue_number: u32,
+    branch_template: &str,
+) -> Vec<String> {
+    let task_template = branch::task_name(branch_template);
+    tasks
+        .iter()
+        .filter(|task| match &task.record {
+            Some(record) => record.issue == Some(issue_number),
+            None => {
+                branch::matches_issue(&task_template, &task.name, issue_number)
+                    || task.branch.as_deref().is_some_and(|branch| {
+                        branch::matches_issue(branch_template, branch, issue_number)
+                    })
+            }
+        })
+        .map(|task| task.name.clone())
+        .collect()
+}
 
-    if !output.status.success() {
-        bail!("base ディレクトリのブランチを取得できませんでした");
+fn prompt_context(
+    manager: &RepoManager,
+    issue_number: u32,
+    issue: Option<&Issue>,
+    branch_name: &str,
+    base_branch: &str,
+    ai_tool: &AiTool,
+    plan: bool,
+) -> PromptContext {
+    let mut context = PromptContext {
+        issue_number,
+        branch: branch_name.to_string(),
+        base_branch: base_branch.to_string(),
+        repo: prompt::repo_slug(&manager.config.repo_url),
+        tool: ai_tool.name.clone(),
+        plan,
+        ..Default::default()
+    };
+    if let Some(issue) = issue {
+        context.set_issue(issue, &manager.config.prompt);
     }
+    context
+}
 
-    let branch = String::from_utf8_lossy(&output.stdout).trim().to_string();
+/// forge から Issue を取得する (オフラインモードでは確認せず None を返す)
+pub fn fetch_issue(issue_number: u32, manager: &RepoManager) -> Result<Option<Issue>> {
+    let forge = forge::for_manager(manager);
+    if manager.offline && forge.is_remote() {
+        eprintln!("オフラインモードのため Issue #{} の確認をスキップします", issue_number);
+        return Ok(None);
+    }
+    forge.check_available()?;
+    let issue = forge.view_issue(issue_number)?;
+    eprintln!("✓ Issue #{} を確認しました: {}", issue_number, issue.title);
+    Ok(Some(issue))
+}
+
+fn determine_base_branch(manager: &RepoManager, options: &StartOptions) -> Result<String> {
+    let branch = select_base_branch(manager, options)?;
     println!("✓ ベースブランチ: {}", branch);
     Ok(branch)
 }
 
-fn handle_branch_conflict(branch_name: &str, issue_number: u32) -> Result<String> {
-    println!("ブランチ \"{}\" は既に存在します。", branch_name);
-    println!("選択してください:");
-    println!("  1. 既存のワークスペースに切り替える");
-    println!("  2. 新しいブランチ名で作成する (例: aicloner-issue{}-2)", issue_number);
-    println!("  3. キャンセル");
-
-    print!("選択 [1-3]: ");
-    io::stdout().flush()?;
-
-    let mut input = String::new();
-    io::stdin().read_line(&mut input)?;
-
-    match input.trim() {
-        "1" => Ok(branch_name.to_string()),
-        "2" => {
-            print!("新しいブランチ名を入力してください: ");
-            io::stdout().flush()?;
-            let mut new_branch = String::new();
-            io::stdin().read_line(&mut new_branch)?;
-            let new_branch = new_branch.trim().to_string();
-            if new_branch.is_empty() {
-                bail!("ブランチ名が空です");
-            }
-            Ok(new_branch)
+/// `--from` で指定したブランチ (リモートに無ければエラー)、無ければ既定のブランチ
+fn select_base_branch(manager: &RepoManager, options: &StartOptions) -> Result<String> {
+    let Some(branch) = &options.base_branch else {
+        return manager.default_branch();
+    };
+    if !manager.remote_branch_exists(branch)? {
+        bail!("ベースブランチ \"{}\" がリモートにありません", branch);
+    }
+    Ok(branch.clone())
+}
+
+/// 既存のブランチとの衝突の解決方法
+#[derive(Debug, PartialEq, Eq)]
+enum ConflictResolution {
+    /// 既存のワークスペースでセッションを再開する
+    Reuse,
+    /// 別のブランチ名で作成する
+    Rename(String),
+}
+
+/// `--on-conflict` に従って衝突を解決する (未指定なら対話で選ぶ)
+fn resolve_conflict(
+    manager: &RepoManager,
+    branch_name: &str,
+    options: &StartOptions,
+) -> Result<ConflictResolution> {
+    let policy = match options.on_conflict {
+        Some(policy) => policy,
+        None if options.interactive => {
+            let suggestion = next_free_branch(manager, branch_name)?;
+            return ask_conflict(
+                &mut io::stdin().lock(),
+                &mut io::stdout(),
+                branch_name,
+                &suggestion,
+            );
+        }
+        None => bail!(
+            "ブランチ \"{}\" は既に存在します。\n\
+             --on-conflict reuse|new|abort|suffix で扱いを指定してください。",
+            branch_name
+        ),
+    };
+    match policy {
+        ConflictPolicy::Reuse => Ok(ConflictResolution::Reuse),
+        ConflictPolicy::Abort => bail!(
+            "ブランチ \"{}\" は既に存在するため中止しました",
+            branch_name
+        ),
+        ConflictPolicy::Suffix => {
+            let name = next_free_branch(manager, branch_name)?;
+            println!("✓ ブランチ名: {}", name);
+            Ok(ConflictResolution::Rename(name))
+        }
+        ConflictPolicy::New if options.interactive => {
+            let suggestion = next_free_branch(manager, branch_name)?;
+            read_branch_name(&mut io::stdin().lock(), &mut io::stdout(), &suggestion)
+                .map(ConflictResolution::Rename)
         }
-        "3" => bail!("操作をキャンセルしました"),
+        ConflictPolicy::New => bail!(
+            "ブランチ \"{}\" は既に存在します。\n\
+             新しいブランチ名を --branch で指定するか、--on-conflict suffix を使ってください。",
+            branch_name
+        ),
+    }
+}
+
+/// `<branch>-2`, `<branch>-3`, … のうち、ワークスペースにもリモートにも無い最初の名前
+fn next_free_branch(manager: &RepoManager, branch_name: &str) -> Result<String> {
+    for n in 2.. {
+        let candidate = format!("{}-{}", branch_name, n);
+        if manager.task_exists(&branch::task_name(&candidate)) {
+            continue;
+        }
+        match manager.remote_branch_exists(&candidate) {
+            Ok(true) => continue,
+            Ok(false) => {}
+            // リモートを確認できなくても、ワークスペースと重ならない名前で進める
+            Err(err) => eprintln!(
+                "警告: リモートのブランチを確認できませんでした: {:#}",
+                err
+            ),
+        }
+        return Ok(candidate);
+    }
+    unreachable!("連番の候補は尽きない")
+}
+
+/// 衝突したときの選択肢を表示して入力を読む
+fn ask_conflict(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    branch_name: &str,
+    suggestion: &str,
+) -> Result<ConflictResolution> {
+    writeln!(output, "ブランチ \"{}\" は既に存在します。", branch_name)?;
+    writeln!(output, "選択してください:")?;
+    writeln!(output, "  1. 既存のワークスペースに切り替える")?;
+    writeln!(output, "  2. 新しいブランチ名で作成する (例: {})", suggestion)?;
+    writeln!(output, "  3. キャンセル")?;
+
+    write!(output, "選択 [1-3]: ")?;
+    output.flush()?;
+
+    let mut choice = String::new();
+    input.read_line(&mut choice)?;
+
+    match choice.trim() {
+        "1" => Ok(ConflictResolution::Reuse),
+        "2" => read_branch_name(input, output, suggestion).map(ConflictResolution::Rename),
         _ => bail!("操作をキャンセルしました"),
     }
 }
 
+/// 新しいブランチ名を読む (空なら `suggestion` を使う)
+fn read_branch_name(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    suggestion: &str,
+) -> Result<String> {
+    write!(
+        output,
+        "新しいブランチ名を入力してください [{}]: ",
+        suggestion
+    )?;
+    output.flush()?;
+    let mut new_branch = String::new();
+    if input.read_line(&mut new_branch)? == 0 {
+        bail!("操作をキャンセルしました");
+    }
+    let new_branch = new_branch.trim();
+    if new_branch.is_empty() {
+        return Ok(suggestion.to_string());
+    }
+    Ok(new_branch.to_string())
+}
+
 fn create_workspace_for_issue(
     manager: &RepoManager,
+    task_name: &str,
     branch_name: &str,
     base_branch: &str,
 ) -> Result<PathBuf> {
-    manager.create_task_clone(branch_name, base_branch)?;
-    Ok(manager.workspaces_dir().join(branch_name))
-}
-
-fn launch_ai_session(workspace_path: &Path, issue_number: u32, ai_tool: AiTool, plan: bool) -> Result<()> {
-    let prompt = if plan {
-        format!(
-            "あなたは優秀なエンジニアです。issue#{}を対応してください。まずplanモードで最初に計画を立ててください。\n\n\
-             - ghコマンドを使ってissueを確認すること\n\
-             - issueに従って適切に実装すること\n\
-             - 適切な粒度でcommitすること\n\
-             - コミットメッセージは日本語で簡潔に書くこと\n\
-             - 疑問点はユーザーに聞くこと\n\
-             - 実装後にプルリクエストを作成する際は、必ず以下の手順を守ること:\n\
-               1. すべての変更をcommitしてワーキングツリーをクリーンにする（git statusで確認）\n\
-               2. リモートブランチにpushする（git push -u origin ブランチ名）\n\
-               3. gh pr createコマンドを実行してプルリクエストを作成する\n\
-               4. プルリクエストには該当issueを紐づけること（Closes #{}を本文に含める）\n\
-               5. プルリクエストはghコマンドで--headフラグを用いて作成すること",
-            issue_number, issue_number
-        )
-    } else {
-        format!(
-            "あなたは優秀なエンジニアです。issue#{}を対応してください。\n\n\
-             - ghコマンドを使ってissueを確認すること\n\
-             - issueに従って適切に実装すること\n\
-             - 適切な粒度でcommitすること\n\
-             - コミットメッセージは日本語で簡潔に書くこと\n\
-             - 疑問点はユーザーに聞くこと\n\
-             - 実装後にプルリクエストを作成する際は、必ず以下の手順を守ること:\n\
-               1. すべての変更をcommitしてワーキングツリーをクリーンにする（git statusで確認）\n\
-               2. リモートブランチにpushする（git push -u origin ブランチ名）\n\
-               3. gh pr createコマンドを実行してプルリクエストを作成する\n\
-               4. プルリクエストには該当issueを紐づけること（Closes #{}を本文に含める）\n\
-               5. プルリクエストはghコマンドで--headフラグを用いて作成すること",
-            issue_number, issue_number
-        )
-    };
+    manager.create_task_workspace(task_name, branch_name, base_branch)?;
+    Ok(manager.workspaces_dir().join(task_name))
+}
 
+fn launch_ai_session(
+    manager: &RepoManager,
+    task_name: &str,
+    workspace_path: &Path,
+    input: SessionInput,
+    ai_tool: &AiTool,
+    options: &StartOptions,
+) -> Result<()> {
     println!("\n{}セッションを起動します...", ai_tool.display_name());
     println!("ワークスペース: {}", workspace_path.display());
 
-    // Change to workspace directory and exec the AI tool
-    std::env::set_current_dir(workspace_path)
-        .with_context(|| format!("ワークスペースへの移動に失敗しました: {}", workspace_path.display()))?;
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::CommandExt;
-        let err = Command::new(ai_tool.executable_command()).arg(&prompt).exec();
-        // exec only returns on error
-        Err(anyhow::anyhow!("{} の起動に失敗しました: {}", ai_tool.display_name(), err))
-    }
-
-    #[cfg(not(unix))]
-    {
-        use which::which;
-
-        // For Windows: directly call node.js to avoid batch file argument limitations
-        // npm-installed commands use .cmd wrapper files that don't handle long/multiline arguments well
-        let command_name = ai_tool.command_name();
-
-        // Try to find the cli.js path from the .cmd file location
-        if let Ok(cmd_path) = which(format!("{}.cmd", command_name)) {
-            // Convert .cmd path to cli.js path
-            // Example: C:\...\npm\claude.cmd -> C:\...\npm\node_modules\@anthropic-ai\claude-code\cli.js
-            if let Some(npm_dir) = cmd_path.parent() {
-                // Determine the package path based on command name
-                let package_path = match command_name {
-                    "claude" => "node_modules\\@anthropic-ai\\claude-code\\cli.js",
-                    "codex" => "node_modules\\codex\\cli.js", // Adjust this based on actual codex package
-                    _ => {
-                        bail!("Unknown command: {}", command_name);
-                    }
-                };
-
-                let cli_js_path = npm_dir.join(package_path);
-
-                if cli_js_path.exists() {
-                    let status = Command::new("node")
-                        .arg(&cli_js_path)
-                        .arg(&prompt)
-                        .status()?;
-
-                    if !status.success() {
-                        bail!("{} が異常終了しました", ai_tool.display_name());
-                    }
-                    return Ok(());
-                }
-            }
+    if options.record {
+        #[cfg(unix)]
+        {
+            let mut log = SessionLog::create(manager, task_name, &ai_tool.name)?;
+            println!("ログ: {}", log.path.display());
+            return ai_tool.launch_recorded(workspace_path, input, options.plan, &mut log);
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (manager, task_name);
+            eprintln!("警告: セッションの記録は Unix でのみ利用できます");
         }
+    }
 
-        // Fallback: try using the .cmd file directly
-        let executable = ai_tool.executable_command();
-        let status = Command::new(&executable)
-            .arg(&prompt)
-            .status()?;
+    ai_tool.launch(workspace_path, input, options.plan)
+}
 
-        if !status.success() {
-            bail!("{} が異常終了しました", ai_tool.display_name());
-        }
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ask(answers: &str) -> Result<ConflictResolution> {
+        let mut output = Vec::new();
+        ask_conflict(
+            &mut answers.as_bytes(),
+            &mut output,
+            "aicloner-issue1",
+            "aicloner-issue1-2",
+        )
+    }
+
+    #[test]
+    fn test_ask_conflict() {
+        assert_eq!(ask("1\n").unwrap(), ConflictResolution::Reuse);
+        assert_eq!(
+            ask("2\naicloner-issue1-retry\n").unwrap(),
+            ConflictResolution::Rename("aicloner-issue1-retry".to_string())
+        );
+        assert_eq!(
+            ask("2\n\n").unwrap(),
+            ConflictResolution::Rename("aicloner-issue1-2".to_string())
+        );
+        assert!(ask("3\n").is_err());
+        // 入力が無ければ待たずにキャンセルする
+        assert!(ask("").is_err());
+        assert!(ask("2\n").is_err());
     }
 }
@@ -0,0 +1,126 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::WorkspaceStrategy;
+
+/// リポジトリごとの作業データを置くディレクトリ (設定ファイルと同じ階層)
+pub const STATE_DIR: &str = ".aicloner";
+pub const STATE_FILE: &str = "state.toml";
+
+/// タスクのメタデータを保持する `.aicloner/state.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct State {
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskRecord {
+    pub branch: String,
+    pub base_branch: String,
+    #[serde(default)]
+    pub strategy: WorkspaceStrategy,
+    /// RFC 3339 形式の作成日時 (UTC)
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// 作成時にチェックアウトしたコミット (prune でマージ済みかの判定に使う)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_commit: Option<String>,
+}
+
+impl State {
+    /// 状態ファイルを読み込む。存在しない場合は空の状態を返す
+    pub fn load(path: &Path) -> Result<State> {
+        if !path.exists() {
+            return Ok(State::default());
+        }
+        let raw = fs::read_to_string(path)
+            .with_context(|| format!("状態ファイルの読み込みに失敗しました: {}", path.display()))?;
+        let state = toml::from_str(&raw)
+            .with_context(|| format!("状態ファイルのパースに失敗しました: {}", path.display()))?;
+        Ok(state)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("状態ディレクトリの作成に失敗しました: {}", parent.display())
+            })?;
+        }
+        let content =
+            toml::to_string_pretty(self).context("状態ファイルのシリアライズに失敗しました")?;
+        fs::write(path, content)
+            .with_context(|| format!("状態ファイルの書き込みに失敗しました: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl TaskRecord {
+    /// 作成からの経過時間 (日時が読めない場合は None)
+    pub fn age(&self) -> Option<Duration> {
+        let created = humantime::parse_rfc3339(&self.created_at).ok()?;
+        SystemTime::now().duration_since(created).ok()
+    }
+}
+
+pub fn timestamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(time).to_string()
+}
+
+pub fn now_timestamp() -> String {
+    timestamp(SystemTime::now())
+}
+
+/// 経過時間を `3d` / `5h` / `12m` のような短い表記にする
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    if secs >= 86_400 {
+        format!("{}d", secs / 86_400)
+    } else if secs >= 3_600 {
+        format!("{}h", secs / 3_600)
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(42)), "42s");
+        assert_eq!(format_age(Duration::from_secs(5 * 60)), "5m");
+        assert_eq!(format_age(Duration::from_secs(3 * 3_600 + 59)), "3h");
+        assert_eq!(format_age(Duration::from_secs(15 * 86_400)), "15d");
+    }
+
+    #[test]
+    fn test_record_age_from_timestamp() {
+        let created = SystemTime::now() - Duration::from_secs(2 * 86_400);
+        let record = TaskRecord {
+            branch: "task-a".to_string(),
+            base_branch: "main".to_string(),
+            strategy: WorkspaceStrategy::Clone,
+            created_at: timestamp(created),
+            issue: None,
+            tool: None,
+            base_commit: None,
+        };
+        let age = record.age().unwrap();
+        assert!(age >= Duration::from_secs(2 * 86_400 - 1));
+        assert_eq!(format_age(age), "2d");
+    }
+}
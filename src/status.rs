@@ -0,0 +1,333 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::{
+    forge::{self, Forge, PullRequest},
+    output::{self, Collection, OutputFormat},
+    repo::{run_command_quiet, RepoManager, TaskInfo},
+    state::format_age,
+};
+
+/// 同時に問い合わせるワークスペース数の上限
+const MAX_WORKERS: usize = 8;
+
+/// ahead / behind のコミット数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Divergence {
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommitSummary {
+    pub subject: String,
+    /// コミット日時 (UNIX 秒)
+    pub timestamp: u64,
+}
+
+/// PR の有無の確認結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PrStatus {
+    /// 確認していない (forge の CLI が無い、オフライン、失敗など)
+    Unknown,
+    NotFound,
+    Found(PullRequest),
+}
+
+/// タスクごとのワークスペースの状態
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub name: String,
+    pub branch: Option<String>,
+    pub base_branch: Option<String>,
+    pub modified: usize,
+    pub untracked: usize,
+    /// 上流ブランチとの差 (上流が無い場合は None)
+    pub upstream: Option<Divergence>,
+    /// ベースブランチとの差 (リモート追跡ブランチが無い場合は None)
+    pub base: Option<Divergence>,
+    pub last_commit: Option<CommitSummary>,
+    pub pull_request: PrStatus,
+    /// git の問い合わせ自体に失敗した場合のエラー
+    pub error: Option<String>,
+}
+
+pub struct StatusOptions {
+    /// forge で PR の有無を確認する
+    pub check_pull_requests: bool,
+}
+
+/// 各タスクの状態を並列に収集する (結果は `tasks` と同じ順序)
+pub fn collect_statuses(
+    manager: &RepoManager,
+    tasks: &[TaskInfo],
+    options: &StatusOptions,
+) -> Vec<TaskStatus> {
+    let forge = forge::for_manager(manager);
+    let check_pull_requests = options.check_pull_requests
+        && !(manager.offline && forge.is_remote())
+        && forge.check_available().is_ok();
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(vec![None; tasks.len()]);
+    let workers = tasks.len().clamp(1, MAX_WORKERS);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(task) = tasks.get(index) else {
+                    break;
+                };
+                let pr_forge = check_pull_requests.then_some(forge.as_ref());
+                let status = collect_status(task, pr_forge);
+                results.lock().expect("status results lock")[index] = Some(status);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .expect("status results lock")
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn collect_status(task: &TaskInfo, forge: Option<&dyn Forge>) -> TaskStatus {
+    let base_branch = task.record.as_ref().map(|r| r.base_branch.clone());
+    let mut status = TaskStatus {
+        name: task.name.clone(),
+        branch: task.branch.clone(),
+        base_branch: base_branch.clone(),
+        modified: 0,
+        untracked: 0,
+        upstream: None,
+        base: None,
+        last_commit: None,
+        pull_request: PrStatus::Unknown,
+        error: None,
+    };
+
+    let git = |args: &[&str]| {
+        let mut full = vec!["-C".to_string(), task.path.display().to_string()];
+        full.extend(args.iter().map(|arg| arg.to_string()));
+        run_command_quiet("git", &full, None)
+    };
+
+    match git(&["status", "--porcelain"]) {
+        Ok(output) => {
+            let (modified, untracked) = count_changes(&output);
+            status.modified = modified;
+            status.untracked = untracked;
+        }
+        Err(err) => {
+            status.error = Some(format!("{:#}", err));
+            return status;
+        }
+    }
+    status.upstream = git(&["rev-list", "--left-right", "--count", "@{upstream}...HEAD"])
+        .ok()
+        .and_then(|output| parse_divergence(&output));
+    if let Some(base_branch) = &base_branch {
+        let range = format!("origin/{}...HEAD", base_branch);
+        status.base = git(&["rev-list", "--left-right", "--count", &range])
+            .ok()
+            .and_then(|output| parse_divergence(&output));
+    }
+    status.last_commit = git(&["log", "-1", "--format=%ct%x09%s"])
+        .ok()
+        .and_then(|output| parse_last_commit(&output));
+
+    if let (Some(forge), Some(branch)) = (forge, &task.branch) {
+        status.pull_request = match forge.find_pull_request(branch) {
+            Ok(Some(pr)) => PrStatus::Found(pr),
+            Ok(None) => PrStatus::NotFound,
+            Err(_) => PrStatus::Unknown,
+        };
+    }
+    status
+}
+
+/// `git status --porcelain` の出力から (変更, 未追跡) の件数を数える
+fn count_changes(porcelain: &str) -> (usize, usize) {
+    porcelain
+        .lines()
+        .filter(|line| !line.is_empty())
+        .fold((0, 0), |(modified, untracked), line| {
+            if line.starts_with("??") {
+                (modified, untracked + 1)
+            } else {
+                (modified + 1, untracked)
+            }
+        })
+}
+
+/// `rev-list --left-right --count A...HEAD` の出力 (`behind<TAB>ahead`) を読む
+fn parse_divergence(output: &str) -> Option<Divergence> {
+    let mut parts = output.split_whitespace();
+    let behind = parts.next()?.parse().ok()?;
+    let ahead = parts.next()?.parse().ok()?;
+    Some(Divergence { ahead, behind })
+}
+
+fn parse_last_commit(output: &str) -> Option<CommitSummary> {
+    let (timestamp, subject) = output.split_once('\t')?;
+    Some(CommitSummary {
+        subject: subject.to_string(),
+        timestamp: timestamp.trim().parse().ok()?,
+    })
+}
+
+/// 状態を指定の形式で出力する
+/// `repos` を渡すと各行にリポジトリ名を付ける (`--all-repos`)
+pub fn print_statuses(
+    statuses: &[TaskStatus],
+    repos: Option<&[String]>,
+    format: OutputFormat,
+) -> Result<()> {
+    if format == OutputFormat::Json {
+        return match repos {
+            Some(repos) => output::print_json_with_repos(Collection::Statuses, statuses, repos),
+            None => output::print_json(Collection::Statuses, statuses),
+        };
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut rows: Vec<Vec<String>> = statuses
+        .iter()
+        .map(|status| {
+            let changes = if status.modified == 0 && status.untracked == 0 {
+                "clean".to_string()
+            } else {
+                format!("M{} U{}", status.modified, status.untracked)
+            };
+            let last = match &status.last_commit {
+                Some(commit) => format!(
+                    "{}前 {}",
+                    format_age(Duration::from_secs(now.saturating_sub(commit.timestamp))),
+                    commit.subject
+                ),
+                None => "-".to_string(),
+            };
+            let pr = match &status.pull_request {
+                PrStatus::Unknown => "?".to_string(),
+                PrStatus::NotFound => "-".to_string(),
+                PrStatus::Found(pr) => format!("#{} {}", pr.number, pr.state),
+            };
+            vec![
+                status.name.clone(),
+                status.branch.clone().unwrap_or_else(|| "-".to_string()),
+                changes,
+                format_divergence(status.upstream),
+                match &status.base_branch {
+                    Some(base) => format!("{} {}", base, format_divergence(status.base)),
+                    None => "-".to_string(),
+                },
+                pr,
+                last,
+            ]
+        })
+        .collect();
+
+    let mut headers = vec![
+        "TASK",
+        "BRANCH",
+        "CHANGES",
+        "UPSTREAM",
+        "BASE",
+        "PR",
+        "LAST COMMIT",
+    ];
+    if let Some(repos) = repos {
+        output::prepend_repo_column(&mut headers, &mut rows, repos);
+    }
+    match format {
+        OutputFormat::Tsv => {
+            headers.push("ERROR");
+            let rows: Vec<Vec<String>> = rows
+                .into_iter()
+                .zip(statuses)
+                .map(|(mut row, status)| {
+                    row.push(status.error.clone().unwrap_or_default());
+                    row
+                })
+                .collect();
+            output::print_tsv(&headers, &rows);
+        }
+        _ => {
+            output::print_table(&headers, &rows);
+            for status in statuses {
+                if let Some(error) = &status.error {
+                    println!("{}: エラー: {}", status.name, error.replace('\n', "\n  "));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn format_divergence(divergence: Option<Divergence>) -> String {
+    match divergence {
+        Some(d) => format!("+{}/-{}", d.ahead, d.behind),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_changes() {
+        let porcelain = " M src/main.rs\nA  new.rs\n?? notes.txt\n?? tmp/\n";
+        assert_eq!(count_changes(porcelain), (2, 2));
+        assert_eq!(count_changes(""), (0, 0));
+    }
+
+    #[test]
+    fn test_parse_divergence() {
+        assert_eq!(
+            parse_divergence("3\t5"),
+            Some(Divergence {
+                ahead: 5,
+                behind: 3
+            })
+        );
+        assert_eq!(parse_divergence(""), None);
+    }
+
+    #[test]
+    fn test_pr_status_json() {
+        let found = PrStatus::Found(PullRequest {
+            number: 7,
+            state: "OPEN".to_string(),
+            url: "https://github.com/o/r/pull/7".to_string(),
+        });
+        let json = serde_json::to_value(&found).unwrap();
+        assert_eq!(json["status"], "found");
+        assert_eq!(json["number"], 7);
+        assert_eq!(
+            serde_json::to_value(&PrStatus::NotFound).unwrap(),
+            serde_json::json!({"status": "not_found"})
+        );
+    }
+
+    #[test]
+    fn test_parse_last_commit() {
+        let commit = parse_last_commit("1700000000\tFix\tthe bug").unwrap();
+        assert_eq!(commit.timestamp, 1_700_000_000);
+        assert_eq!(commit.subject, "Fix\tthe bug");
+    }
+}
@@ -0,0 +1,190 @@
+use std::{fmt, path::Path};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    config::SyncMethod,
+    output,
+    repo::{run_command, run_command_capture, RepoManager},
+};
+
+/// sync の実行オプション
+pub struct SyncOptions {
+    pub method: SyncMethod,
+    /// 未コミットの変更を一時退避して追従する
+    pub autostash: bool,
+}
+
+/// タスクごとの sync 結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncOutcome {
+    UpToDate,
+    Updated,
+    Conflicts,
+    DirtySkipped,
+    Failed(String),
+}
+
+impl fmt::Display for SyncOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SyncOutcome::UpToDate => "最新",
+            SyncOutcome::Updated => "更新",
+            SyncOutcome::Conflicts => "コンフリクト",
+            SyncOutcome::DirtySkipped => "未コミットの変更あり (スキップ)",
+            SyncOutcome::Failed(_) => "失敗",
+        };
+        f.pad(label)
+    }
+}
+
+pub struct TaskSyncResult {
+    pub task_name: String,
+    pub base_branch: String,
+    pub outcome: SyncOutcome,
+}
+
+/// base を最新化し、指定タスクをベースブランチに追従させる
+pub fn sync_tasks(
+    manager: &RepoManager,
+    task_names: &[String],
+    options: &SyncOptions,
+) -> Result<Vec<TaskSyncResult>> {
+    for task_name in task_names {
+        if !manager.task_exists(task_name) {
+            bail!("タスク \"{}\" は存在しません。", task_name);
+        }
+    }
+
+    if manager.mirror_dir().exists() && !manager.offline {
+        manager.fetch_mirror()?;
+    }
+    let default_base = manager.default_branch()?;
+    update_base(manager, &default_base)?;
+
+    let state = manager.load_state()?;
+    let mut results = Vec::new();
+    for task_name in task_names {
+        // 作成時に記録したベースブランチを優先する
+        let base_branch = state
+            .tasks
+            .get(task_name)
+            .map(|record| record.base_branch.clone())
+            .unwrap_or_else(|| default_base.clone());
+        let workspace_dir = manager.workspaces_dir().join(task_name);
+        let outcome = sync_task(manager, &workspace_dir, &base_branch, options)
+            .unwrap_or_else(|err| SyncOutcome::Failed(format!("{:#}", err)));
+        results.push(TaskSyncResult {
+            task_name: task_name.clone(),
+            base_branch,
+            outcome,
+        });
+    }
+    Ok(results)
+}
+
+pub fn print_summary(results: &[TaskSyncResult]) {
+    let rows: Vec<Vec<String>> = results
+        .iter()
+        .map(|result| {
+            vec![
+                result.task_name.clone(),
+                result.base_branch.clone(),
+                result.outcome.to_string(),
+            ]
+        })
+        .collect();
+    println!();
+    output::print_table(&["TASK", "BASE", "RESULT"], &rows);
+    // 失敗の詳細は表の幅を崩さないよう、表の後にまとめて出す
+    for result in results {
+        if let SyncOutcome::Failed(message) = &result.outcome {
+            println!();
+            println!("{}:", result.task_name);
+            println!("  {}", message.replace('\n', "\n  "));
+        }
+    }
+}
+
+/// base のリモート追跡ブランチを更新し、そのブランチをチェックアウトしていれば fast-forward する
+fn update_base(manager: &RepoManager, base_branch: &str) -> Result<()> {
+    let base_str = manager.base_dir().display().to_string();
+    let fetch_args = vec![
+        "-C".to_string(),
+        base_str.clone(),
+        "fetch".to_string(),
+        manager.fetch_source()?,
+        format!("+refs/heads/{0}:refs/remotes/origin/{0}", base_branch),
+    ];
+    run_command("git", &fetch_args, None)?;
+    // 別のブランチをチェックアウトしている base に既定のブランチをマージしない
+    if manager.current_base_branch()? != base_branch {
+        return Ok(());
+    }
+    let merge_args = vec![
+        "-C".to_string(),
+        base_str,
+        "merge".to_string(),
+        "--ff-only".to_string(),
+        format!("origin/{}", base_branch),
+    ];
+    run_command("git", &merge_args, None)?;
+    Ok(())
+}
+
+fn sync_task(
+    manager: &RepoManager,
+    workspace_dir: &Path,
+    base_branch: &str,
+    options: &SyncOptions,
+) -> Result<SyncOutcome> {
+    let repo = workspace_dir.display().to_string();
+    let git = |args: &[&str]| -> Vec<String> {
+        let mut full = vec!["-C".to_string(), repo.clone()];
+        full.extend(args.iter().map(|arg| arg.to_string()));
+        full
+    };
+
+    let status = run_command_capture("git", &git(&["status", "--porcelain"]), None)?;
+    if !status.is_empty() && !options.autostash {
+        return Ok(SyncOutcome::DirtySkipped);
+    }
+
+    // worktree では base とリモート追跡ブランチを共有するため、base 側も更新される
+    let refspec = format!("+refs/heads/{0}:refs/remotes/origin/{0}", base_branch);
+    let source = manager.fetch_source()?;
+    run_command("git", &git(&["fetch", &source, &refspec]), None)?;
+
+    let upstream = format!("origin/{}", base_branch);
+    let is_ancestor = git(&["merge-base", "--is-ancestor", &upstream, "HEAD"]);
+    if run_command("git", &is_ancestor, None).is_ok() {
+        return Ok(SyncOutcome::UpToDate);
+    }
+
+    let (command, abort) = match options.method {
+        SyncMethod::Rebase => ("rebase", git(&["rebase", "--abort"])),
+        SyncMethod::Merge => ("merge", git(&["merge", "--abort"])),
+    };
+    let mut update = git(&[command]);
+    if options.method == SyncMethod::Merge {
+        update.push("--no-edit".to_string());
+    }
+    if options.autostash {
+        update.push("--autostash".to_string());
+    }
+    update.push(upstream);
+
+    if let Err(err) = run_command("git", &update, None) {
+        let unmerged = git(&["diff", "--name-only", "--diff-filter=U"]);
+        let conflicted = run_command_capture("git", &unmerged, None)
+            .map(|output| !output.is_empty())
+            .unwrap_or(false);
+        // 途中状態を残さず、元のブランチに戻す
+        let _ = run_command("git", &abort, None);
+        if conflicted {
+            return Ok(SyncOutcome::Conflicts);
+        }
+        return Err(err);
+    }
+    Ok(SyncOutcome::Updated)
+}
@@ -0,0 +1,383 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{
+    config::WorkspaceStrategy,
+    repo::{run_command, run_command_capture, RepoManager, TaskInfo},
+};
+
+/// ワークスペース作成時の指定内容
+pub struct WorkspaceRequest<'a> {
+    pub workspace_dir: &'a Path,
+    pub branch: &'a str,
+    pub base_branch: &'a str,
+    /// `branch` と同名のブランチがリモートに存在するか
+    pub remote_branch_exists: bool,
+}
+
+/// タスク用ワークスペースの作成・削除・列挙を行うバックエンド
+pub trait WorkspaceBackend {
+    fn strategy(&self) -> WorkspaceStrategy;
+
+    fn create(&self, manager: &RepoManager, request: &WorkspaceRequest) -> Result<()>;
+
+    fn remove(&self, manager: &RepoManager, workspace_dir: &Path) -> Result<()>;
+
+    /// このバックエンドで管理されているタスクを列挙する
+    fn list(&self, manager: &RepoManager) -> Result<Vec<TaskInfo>>;
+}
+
+/// 指定した方式のバックエンドを返す
+pub fn backend(strategy: WorkspaceStrategy) -> &'static dyn WorkspaceBackend {
+    match strategy {
+        WorkspaceStrategy::Clone => &CloneBackend,
+        WorkspaceStrategy::Worktree => &WorktreeBackend,
+    }
+}
+
+/// 既存ワークスペースの作成方式を判定する
+/// worktree では `.git` がディレクトリではなく `gitdir:` を記したファイルになる
+pub fn detect_strategy(workspace_dir: &Path) -> WorkspaceStrategy {
+    if workspace_dir.join(".git").is_file() {
+        WorkspaceStrategy::Worktree
+    } else {
+        WorkspaceStrategy::Clone
+    }
+}
+
+/// `git clone --single-branch` でタスクごとに独立した clone を作成する
+pub struct CloneBackend;
+
+impl WorkspaceBackend for CloneBackend {
+    fn strategy(&self) -> WorkspaceStrategy {
+        WorkspaceStrategy::Clone
+    }
+
+    fn create(&self, manager: &RepoManager, request: &WorkspaceRequest) -> Result<()> {
+        let workspace_dir = request.workspace_dir;
+        let repo_dir_str = workspace_dir.display().to_string();
+        let clone_branch = if request.remote_branch_exists {
+            request.branch
+        } else {
+            request.base_branch
+        };
+
+        let mut clone_args = vec!["clone".to_string()];
+        if let Some(reference) = manager.reference_repo()? {
+            // ローカルパスの clone はハードリンクで全オブジェクトを複製するため
+            // --no-local で通常の転送に切り替え、alternates を実際に使わせる
+            clone_args.extend([
+                "--no-local".to_string(),
+                "--reference-if-able".to_string(),
+                reference.display().to_string(),
+            ]);
+            if manager.config.dissociate {
+                clone_args.push("--dissociate".to_string());
+            }
+        }
+        let source = manager.fetch_source()?;
+        clone_args.extend([
+            "--branch".to_string(),
+            clone_branch.to_string(),
+            "--single-branch".to_string(),
+            source.clone(),
+            repo_dir_str.clone(),
+        ]);
+        run_command("git", &clone_args, None).inspect_err(|_err| {
+            if workspace_dir.exists() {
+                let _ = fs::remove_dir_all(workspace_dir);
+            }
+        })?;
+        // push はミラーではなく本来のリモートに向ける
+        manager.point_origin_upstream(workspace_dir, &source)?;
+
+        if !request.remote_branch_exists {
+            let branch_args = vec![
+                "-C".to_string(),
+                repo_dir_str,
+                "checkout".to_string(),
+                "-b".to_string(),
+                request.branch.to_string(),
+            ];
+            run_command("git", &branch_args, None)?;
+        }
+        Ok(())
+    }
+
+    fn remove(&self, _manager: &RepoManager, workspace_dir: &Path) -> Result<()> {
+        fs::remove_dir_all(workspace_dir).with_context(|| {
+            format!(
+                "ディレクトリの削除に失敗しました: {}",
+                workspace_dir.display()
+            )
+        })
+    }
+
+    fn list(&self, manager: &RepoManager) -> Result<Vec<TaskInfo>> {
+        let mut tasks = Vec::new();
+        for (name, path) in task_dirs(manager, self.strategy())? {
+            let args = vec![
+                "-C".to_string(),
+                path.display().to_string(),
+                "rev-parse".to_string(),
+                "--abbrev-ref".to_string(),
+                "HEAD".to_string(),
+            ];
+            let branch = run_command_capture("git", &args, None)
+                .ok()
+                .map(|output| output.trim().to_string());
+            tasks.push(TaskInfo {
+                name,
+                path,
+                branch,
+                strategy: self.strategy(),
+                record: None,
+            });
+        }
+        Ok(tasks)
+    }
+}
+
+/// base の clone から `git worktree add` でワークスペースを作成する
+pub struct WorktreeBackend;
+
+impl WorkspaceBackend for WorktreeBackend {
+    fn strategy(&self) -> WorkspaceStrategy {
+        WorkspaceStrategy::Worktree
+    }
+
+    fn create(&self, manager: &RepoManager, request: &WorkspaceRequest) -> Result<()> {
+        let base_dir = manager.base_dir();
+        if !base_dir.exists() {
+            bail!(
+                "worktree 方式には base ディレクトリが必要です。先に init を実行してください: {}",
+                base_dir.display()
+            );
+        }
+        let base_str = base_dir.display().to_string();
+        // worktree add は base からの相対パスで解釈されるため絶対パスに変換する
+        let workspace_dir = std::path::absolute(request.workspace_dir).with_context(|| {
+            format!(
+                "ワークスペースのパス解決に失敗しました: {}",
+                request.workspace_dir.display()
+            )
+        })?;
+        let workspace_str = workspace_dir.display().to_string();
+
+        // base は --single-branch の clone なので起点ブランチを明示的に取得する
+        let start_branch = if request.remote_branch_exists {
+            request.branch
+        } else {
+            request.base_branch
+        };
+        let fetch_args = vec![
+            "-C".to_string(),
+            base_str.clone(),
+            "fetch".to_string(),
+            manager.fetch_source()?,
+            format!("+refs/heads/{0}:refs/remotes/origin/{0}", start_branch),
+        ];
+        run_command("git", &fetch_args, None)?;
+
+        let mut add_args = vec![
+            "-C".to_string(),
+            base_str.clone(),
+            "worktree".to_string(),
+            "add".to_string(),
+        ];
+        if local_branch_exists(&base_str, request.branch) {
+            add_args.extend([workspace_str, request.branch.to_string()]);
+        } else {
+            add_args.extend([
+                "--no-track".to_string(),
+                "-b".to_string(),
+                request.branch.to_string(),
+                workspace_str,
+                format!("origin/{}", start_branch),
+            ]);
+        }
+        run_command("git", &add_args, None).inspect_err(|_err| {
+            if workspace_dir.exists() {
+                let _ = fs::remove_dir_all(&workspace_dir);
+            }
+            let _ = prune_worktrees(&base_str);
+        })?;
+
+        if request.remote_branch_exists {
+            // single-branch の clone では --track が使えないため上流を直接設定する
+            for (key, value) in [
+                ("remote", "origin".to_string()),
+                ("merge", format!("refs/heads/{}", request.branch)),
+            ] {
+                let config_args = vec![
+                    "-C".to_string(),
+                    base_str.clone(),
+                    "config".to_string(),
+                    format!("branch.{}.{}", request.branch, key),
+                    value,
+                ];
+                run_command("git", &config_args, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove(&self, manager: &RepoManager, workspace_dir: &Path) -> Result<()> {
+        let base_dir = manager.base_dir();
+        if !base_dir.exists() {
+            // base が無い場合は登録情報を消せないのでディレクトリのみ削除する
+            return CloneBackend.remove(manager, workspace_dir);
+        }
+        let base_str = base_dir.display().to_string();
+        let workspace_dir = std::path::absolute(workspace_dir)?;
+        let remove_args = vec![
+            "-C".to_string(),
+            base_str.clone(),
+            "worktree".to_string(),
+            "remove".to_string(),
+            "--force".to_string(),
+            workspace_dir.display().to_string(),
+        ];
+        run_command("git", &remove_args, None)?;
+        prune_worktrees(&base_str)
+    }
+
+    fn list(&self, manager: &RepoManager) -> Result<Vec<TaskInfo>> {
+        let dirs = task_dirs(manager, self.strategy())?;
+        if dirs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let registered = registered_worktrees(manager);
+        let tasks = dirs
+            .into_iter()
+            .map(|(name, path)| {
+                let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+                let branch = registered.get(&key).cloned();
+                TaskInfo {
+                    name,
+                    path,
+                    branch,
+                    strategy: self.strategy(),
+                    record: None,
+                }
+            })
+            .collect();
+        Ok(tasks)
+    }
+}
+
+/// ワークスペースディレクトリ直下から指定方式のタスクディレクトリを集める
+fn task_dirs(manager: &RepoManager, strategy: WorkspaceStrategy) -> Result<Vec<(String, PathBuf)>> {
+    let workspaces_dir = manager.workspaces_dir();
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(&workspaces_dir).with_context(|| {
+        format!(
+            "ワークスペース一覧の取得に失敗しました: {}",
+            workspaces_dir.display()
+        )
+    })? {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_dir() || detect_strategy(&path) != strategy {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        dirs.push((name, path));
+    }
+    Ok(dirs)
+}
+
+/// `git worktree list --porcelain` から worktree のパスとブランチの対応を得る
+fn registered_worktrees(manager: &RepoManager) -> HashMap<PathBuf, String> {
+    let base_dir = manager.base_dir();
+    if !base_dir.exists() {
+        return HashMap::new();
+    }
+    let args = vec![
+        "-C".to_string(),
+        base_dir.display().to_string(),
+        "worktree".to_string(),
+        "list".to_string(),
+        "--porcelain".to_string(),
+    ];
+    match run_command_capture("git", &args, None) {
+        Ok(output) => parse_worktree_list(&output),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn parse_worktree_list(output: &str) -> HashMap<PathBuf, String> {
+    let mut worktrees = HashMap::new();
+    for block in output.split("\n\n") {
+        let mut path = None;
+        let mut branch = None;
+        for line in block.lines() {
+            if let Some(value) = line.strip_prefix("worktree ") {
+                path = Some(PathBuf::from(value));
+            } else if let Some(value) = line.strip_prefix("branch ") {
+                branch = Some(value.trim_start_matches("refs/heads/").to_string());
+            } else if line == "detached" {
+                // rev-parse --abbrev-ref HEAD と同じ表記に揃える
+                branch = Some("HEAD".to_string());
+            }
+        }
+        if let (Some(path), Some(branch)) = (path, branch) {
+            let key = fs::canonicalize(&path).unwrap_or(path);
+            worktrees.insert(key, branch);
+        }
+    }
+    worktrees
+}
+
+fn local_branch_exists(repo: &str, branch: &str) -> bool {
+    let args = vec![
+        "-C".to_string(),
+        repo.to_string(),
+        "show-ref".to_string(),
+        "--verify".to_string(),
+        "--quiet".to_string(),
+        format!("refs/heads/{}", branch),
+    ];
+    run_command("git", &args, None).is_ok()
+}
+
+fn prune_worktrees(repo: &str) -> Result<()> {
+    let args = vec![
+        "-C".to_string(),
+        repo.to_string(),
+        "worktree".to_string(),
+        "prune".to_string(),
+    ];
+    run_command("git", &args, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_worktree_list() {
+        let output = "worktree /tmp/repo/base\nHEAD 1111\nbranch refs/heads/main\n\n\
+                      worktree /tmp/repo/ws/task-a\nHEAD 2222\nbranch refs/heads/task-a\n\n\
+                      worktree /tmp/repo/ws/task-b\nHEAD 3333\ndetached";
+        let worktrees = parse_worktree_list(output);
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(
+            worktrees
+                .get(Path::new("/tmp/repo/ws/task-a"))
+                .map(String::as_str),
+            Some("task-a")
+        );
+        assert_eq!(
+            worktrees
+                .get(Path::new("/tmp/repo/ws/task-b"))
+                .map(String::as_str),
+            Some("HEAD")
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! 結合テストで共有するリポジトリと `aicloner` バイナリの実行環境
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+use anyhow::Result;
+
+use aicloner::config::Config;
+use aicloner::repo::RepoManager;
+
+pub fn init_remote_repo(tmp: &TempDir) -> Result<PathBuf> {
+    let remote_path = tmp.path().join("remote.git");
+    fs::create_dir(&remote_path)?;
+
+    let init_args = vec!["init".to_string(), "--bare".to_string()];
+    std::process::Command::new("git")
+        .args(&init_args)
+        .current_dir(&remote_path)
+        .output()?;
+
+    let dummy_path = tmp.path().join("dummy");
+    fs::create_dir(&dummy_path)?;
+    std::process::Command::new("git")
+        .args(["init"])
+        .current_dir(&dummy_path)
+        .output()?;
+
+    fs::write(dummy_path.join("README.md"), "# Test")?;
+    std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "add", "."])
+        .current_dir(&dummy_path)
+        .output()?;
+    std::process::Command::new("git")
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "commit", "-m", "init"])
+        .current_dir(&dummy_path)
+        .output()?;
+    std::process::Command::new("git")
+        .args(["branch", "-M", "main"])
+        .current_dir(&dummy_path)
+        .output()?;
+    std::process::Command::new("git")
+        .args(["remote", "add", "origin", remote_path.to_str().unwrap()])
+        .current_dir(&dummy_path)
+        .output()?;
+    std::process::Command::new("git")
+        .args(["push", "-u", "origin", "main"])
+        .current_dir(&dummy_path)
+        .output()?;
+
+    Ok(remote_path)
+}
+
+/// `start` をバイナリごと実行するための環境
+/// (fixture forge で Issue を返し、引数を書き出すだけのスタブを AI ツールとして起動する)
+#[cfg(unix)]
+pub struct StartEnv {
+    pub tmp: TempDir,
+    pub config_path: PathBuf,
+    pub fixture_path: PathBuf,
+    pub stub_output: PathBuf,
+}
+
+#[cfg(unix)]
+impl StartEnv {
+    pub fn new() -> Result<Self> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp = TempDir::new()?;
+        let remote = init_remote_repo(&tmp)?;
+
+        let stub = tmp.path().join("stub-tool.sh");
+        fs::write(
+            &stub,
+            "#!/bin/sh\n{ pwd; printf '%s\\n' \"$@\"; } | tee \"$STUB_OUTPUT\"\n",
+        )?;
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755))?;
+
+        let config_path = tmp.path().join(".aicloner.toml");
+        fs::write(
+            &config_path,
+            format!(
+                "repo_url = \"{}\"\nbase_dir = \"base\"\nworkspaces_dir = \"ws\"\n\
+                 default_tool = \"stub\"\n\n\
+                 [tools.stub]\nexecutable = \"{}\"\nversion_args = []\nprompt = \"arg\"\n\
+                 headless_args = [\"--headless\"]\nresume_args = [\"--resume\"]\n",
+                remote.display(),
+                stub.display()
+            ),
+        )?;
+        let manager = RepoManager::new(Config::load(&config_path)?, config_path.clone());
+        manager.init_environment("main")?;
+
+        let fixture_path = tmp.path().join("forge.toml");
+        fs::write(
+            &fixture_path,
+            "[[issues]]\nnumber = 1\ntitle = \"Fix login\"\nbody = \"Safari で押せない\"\n\
+             state = \"OPEN\"\nlabels = [\"bug\"]\n\n\
+             [[issues]]\nnumber = 2\ntitle = \"Update docs\"\nstate = \"OPEN\"\n\
+             labels = [\"docs\"]\nassignees = [\"alice\"]\n\n\
+             [[issues]]\nnumber = 3\ntitle = \"Old bug\"\nstate = \"CLOSED\"\nlabels = [\"bug\"]\n",
+        )?;
+        let stub_output = tmp.path().join("stub-output.txt");
+        Ok(Self {
+            tmp,
+            config_path,
+            fixture_path,
+            stub_output,
+        })
+    }
+
+    pub fn run(&self, args: &[&str], stdin: &str) -> Result<std::process::Output> {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_aicloner"))
+            .args(args)
+            .arg("--config")
+            .arg(&self.config_path)
+            .current_dir(self.tmp.path())
+            .env("AICLONER_CONFIG_HOME", self.tmp.path().join("config-home"))
+            .env("AICLONER_FORGE_FIXTURE", &self.fixture_path)
+            .env("STUB_OUTPUT", &self.stub_output)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        child.stdin.take().unwrap().write_all(stdin.as_bytes())?;
+        Ok(child.wait_with_output()?)
+    }
+
+    /// `--config` を付けずに `dir` で実行するコマンド (設定ファイルの探索を確かめる)
+    pub fn command_in(&self, dir: &std::path::Path) -> std::process::Command {
+        let mut command = std::process::Command::new(env!("CARGO_BIN_EXE_aicloner"));
+        command
+            .current_dir(dir)
+            .env_remove("AICLONER_CONFIG")
+            .env("AICLONER_CONFIG_HOME", self.tmp.path().join("config-home"))
+            .env("AICLONER_FORGE_FIXTURE", &self.fixture_path)
+            .env("STUB_OUTPUT", &self.stub_output)
+            .stdin(std::process::Stdio::null());
+        command
+    }
+
+    pub fn manager(&self) -> Result<RepoManager> {
+        Ok(RepoManager::new(
+            Config::load(&self.config_path)?,
+            self.config_path.clone(),
+        ))
+    }
+}
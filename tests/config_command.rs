@@ -0,0 +1,153 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+use tempfile::TempDir;
+
+use anyhow::Result;
+
+use common::StartEnv;
+
+#[test]
+fn commands_find_config_and_current_task_from_workspace() -> Result<()> {
+    let env = StartEnv::new()?;
+    assert!(env.run(&["start", "1"], "")?.status.success());
+    assert!(env.run(&["start", "2"], "")?.status.success());
+    let manager = env.manager()?;
+    let nested = manager.workspaces_dir().join("aicloner-issue1").join("src");
+    fs::create_dir_all(&nested)?;
+
+    // 親ディレクトリの設定ファイルを見つけ、引数が無ければ現在のタスクを対象にする
+    let output = env.command_in(&nested).args(["status", "--no-pr"]).output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("aicloner-issue1"), "{}", stdout);
+    assert!(!stdout.contains("aicloner-issue2"), "{}", stdout);
+
+    let output = env.command_in(&nested).arg("resume").output()?;
+    assert!(output.status.success());
+    let launched = fs::read_to_string(&env.stub_output)?;
+    assert!(launched.ends_with("--resume\n"), "{}", launched);
+
+    // 設定ファイルの外では AICLONER_CONFIG で指定する
+    let outside = TempDir::new()?;
+    let output = env.command_in(outside.path()).arg("list").output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("aicloner リポジトリではありません"));
+    let output = env
+        .command_in(outside.path())
+        .arg("list")
+        .env("AICLONER_CONFIG", &env.config_path)
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("aicloner-issue2"), "{}", stdout);
+
+    let output = env.run(&["rm", ".", "--force"], "")?;
+    assert!(!output.status.success());
+    let output = env
+        .command_in(&nested)
+        .args(["rm", ".", "--force"])
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!manager.task_exists("aicloner-issue1"));
+    assert!(manager.task_exists("aicloner-issue2"));
+    Ok(())
+}
+
+#[test]
+fn config_is_migrated_and_checked() -> Result<()> {
+    let env = StartEnv::new()?;
+    // version の無い設定ファイルは読み込み時に移行し、元の内容を残す
+    let backup = env.tmp.path().join(".aicloner.toml.v0.bak");
+    assert!(fs::read_to_string(&backup)?.starts_with("repo_url = "));
+    let migrated = fs::read_to_string(&env.config_path)?;
+    assert!(migrated.starts_with("version = 1\n"), "{}", migrated);
+
+    let output = env.run(&["config", "check"], "")?;
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("問題は見つかりませんでした"));
+
+    fs::write(
+        &env.config_path,
+        migrated.replace("workspaces_dir = \"ws\"", "workspace_dir = \"ws\""),
+    )?;
+    let output = env.run(&["config", "check"], "")?;
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("不明なキー workspace_dir (workspaces_dir の誤りではありませんか?)"),
+        "{}",
+        stdout
+    );
+    let output = env.run(&["list"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("不明なキー workspace_dir"));
+    Ok(())
+}
+
+#[test]
+fn config_subcommands_edit_the_file_in_place() -> Result<()> {
+    let env = StartEnv::new()?;
+    let dir = env.tmp.path();
+    let config = |args: &[&str]| env.command_in(dir).arg("config").args(args).output();
+
+    let output = config(&["set", "prompt.max_chars", "100"])?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let raw = fs::read_to_string(&env.config_path)?;
+    assert!(raw.starts_with("version = 1\nrepo_url = "), "{}", raw);
+    assert!(raw.ends_with("[prompt]\nmax_chars = 100\n"), "{}", raw);
+    let output = config(&["get", "prompt.max_chars"])?;
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "100\n");
+    let output = config(&["get", "workspace_dir"])?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("workspaces_dir の誤り"));
+
+    let output = config(&["list", "--show-origin"])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = |key: &str| {
+        stdout
+            .lines()
+            .find(|line| line.starts_with(&format!("{} ", key)))
+            .unwrap_or_default()
+            .to_string()
+    };
+    assert!(line("prompt.max_chars").ends_with(&env.config_path.display().to_string()));
+    assert!(line("prompt.max_comments").ends_with("default"), "{}", stdout);
+    assert!(line("tools.codex.executable").ends_with("default"), "{}", stdout);
+
+    // 検証に通らない編集は破棄し、通った編集だけを保存する
+    let output = env
+        .command_in(dir)
+        .args(["config", "edit"])
+        .env("VISUAL", "sed -i s/max_chars/max_chars_typo/")
+        .output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("max_chars_typo"));
+    assert_eq!(fs::read_to_string(&env.config_path)?, raw);
+    assert!(!dir.join(".aicloner.edit.toml").exists());
+    let output = env
+        .command_in(dir)
+        .args(["config", "edit"])
+        .env("VISUAL", "sed -i s/100/200/")
+        .output()?;
+    assert!(output.status.success());
+    assert!(fs::read_to_string(&env.config_path)?.contains("max_chars = 200"));
+
+    assert!(config(&["unset", "prompt.max_chars"])?.status.success());
+    assert!(!fs::read_to_string(&env.config_path)?.contains("[prompt]"));
+    assert!(!config(&["unset", "repo_url"])?.status.success());
+    Ok(())
+}
@@ -0,0 +1,73 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+
+use common::StartEnv;
+
+#[test]
+fn issues_filters_and_annotates_workspaces() -> Result<()> {
+    let env = StartEnv::new()?;
+    assert!(env.run(&["start", "1"], "")?.status.success());
+
+    let numbers = |args: &[&str]| -> Result<Vec<(u64, Vec<String>)>> {
+        let output = env.run(args, "")?;
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+        Ok(json["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|issue| {
+                let workspaces = issue["workspaces"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|name| name.as_str().unwrap().to_string())
+                    .collect();
+                (issue["number"].as_u64().unwrap(), workspaces)
+            })
+            .collect())
+    };
+
+    assert_eq!(
+        numbers(&["issues", "--format", "json"])?,
+        vec![
+            (1, vec!["aicloner-issue1".to_string()]),
+            (2, Vec::new())
+        ]
+    );
+    let bugs = numbers(&["issues", "--format", "json", "--label", "bug", "--state", "all"])?;
+    assert_eq!(bugs.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![1, 3]);
+    let assigned = numbers(&["issues", "--format", "json", "--assignee", "alice"])?;
+    assert_eq!(assigned.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![2]);
+    let searched = numbers(&["issues", "--format", "json", "--search", "DOCS", "--limit", "1"])?;
+    assert_eq!(searched.iter().map(|(n, _)| *n).collect::<Vec<_>>(), vec![2]);
+    Ok(())
+}
+
+#[test]
+fn issues_pick_starts_the_chosen_issue() -> Result<()> {
+    let env = StartEnv::new()?;
+    let output = env.run(&["issues", "--pick"], "#2\n")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(env.manager()?.task_exists("aicloner-issue2"));
+    assert!(fs::read_to_string(&env.stub_output)?.contains("Update docs"));
+
+    // 一覧に無い番号は拒否する
+    let output = env.run(&["issues", "--pick"], "3\n")?;
+    assert!(!output.status.success());
+    assert!(!env.manager()?.task_exists("aicloner-issue3"));
+    Ok(())
+}
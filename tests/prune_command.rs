@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use aicloner::{
+    config::Config,
+    prune::{self, PruneOptions, PruneReason},
+    repo::RepoManager,
+};
+use anyhow::{bail, Context, Result};
+use tempfile::TempDir;
+
+fn git_command(dir: Option<&Path>) -> Command {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    cmd.env("GIT_CONFIG_GLOBAL", null_path)
+        .env("GIT_CONFIG_SYSTEM", null_path)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_ATTR_NOSYSTEM", "1");
+    cmd
+}
+
+#[test]
+fn prune_detects_merged_and_squash_merged_tasks() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, tmp.path().join(".aicloner.toml"));
+    manager.init_environment("main")?;
+    for task in ["task-fresh", "task-merged", "task-open", "task-squash"] {
+        manager.create_task_clone(task, "main")?;
+    }
+
+    let merged = manager.workspaces_dir().join("task-merged");
+    commit_file(&merged, "merged.txt", "merged\n")?;
+    run_git(&["push", "origin", "task-merged:main"], Some(&merged))?;
+
+    let open = manager.workspaces_dir().join("task-open");
+    commit_file(&open, "open.txt", "open\n")?;
+
+    let squash = manager.workspaces_dir().join("task-squash");
+    commit_file(&squash, "squash.txt", "one\n")?;
+    commit_file(&squash, "squash.txt", "one\ntwo\n")?;
+
+    // 別の clone から同じ変更を 1 コミットにまとめて main に入れる
+    let other = tmp.path().join("other");
+    run_git(
+        &[
+            "clone",
+            remote.to_string_lossy().as_ref(),
+            other.to_string_lossy().as_ref(),
+        ],
+        None,
+    )?;
+    commit_file(&other, "squash.txt", "one\ntwo\n")?;
+    run_git(&["push", "origin", "main"], Some(&other))?;
+
+    // --dry-run はリモート追跡ブランチもオブジェクトも書き込まない
+    let objects = git_output(&["count-objects"], &squash)?;
+    let dry_run = PruneOptions {
+        older_than: None,
+        check_forge: false,
+        fetch: false,
+    };
+    prune::find_candidates(&manager, &dry_run)?;
+    assert_eq!(git_output(&["count-objects"], &squash)?, objects);
+
+    manager.fetch_mirror()?;
+    let options = PruneOptions {
+        older_than: None,
+        check_forge: false,
+        fetch: true,
+    };
+    let candidates = prune::find_candidates(&manager, &options)?;
+    let found: Vec<(&str, &PruneReason)> = candidates
+        .iter()
+        .map(|c| (c.task.name.as_str(), &c.reason))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("task-merged", &PruneReason::Merged),
+            ("task-squash", &PruneReason::SquashMerged),
+        ]
+    );
+
+    let recent_only = PruneOptions {
+        older_than: Some(Duration::from_secs(14 * 86_400)),
+        check_forge: false,
+        fetch: true,
+    };
+    assert!(prune::find_candidates(&manager, &recent_only)?.is_empty());
+
+    // squash merge したブランチのコミットはどのリモートにも無いため、安全確認で残る
+    assert_eq!(prune::remove_candidates(&manager, &candidates, false), 1);
+    assert!(!merged.exists());
+    assert!(squash.exists());
+
+    assert_eq!(prune::remove_candidates(&manager, &candidates[1..], true), 1);
+    assert!(!squash.exists());
+    assert!(open.exists());
+    Ok(())
+}
+
+fn commit_file(repo: &Path, file: &str, content: &str) -> Result<()> {
+    run_git(&["config", "user.name", "tester"], Some(repo))?;
+    run_git(&["config", "user.email", "tester@example.com"], Some(repo))?;
+    fs::write(repo.join(file), content)?;
+    run_git(&["add", file], Some(repo))?;
+    run_git(&["commit", "-m", &format!("Update {}", file)], Some(repo))?;
+    Ok(())
+}
+
+fn init_remote_repo(tmp: &TempDir) -> Result<PathBuf> {
+    let remote = tmp.path().join("remote.git");
+    run_git(&["init", "--bare", remote.to_string_lossy().as_ref()], None)?;
+    run_git(&["symbolic-ref", "HEAD", "refs/heads/main"], Some(&remote))?;
+
+    let seed = tmp.path().join("seed");
+    fs::create_dir_all(&seed)?;
+    run_git(&["init"], Some(&seed))?;
+    commit_file(&seed, "README.md", "hello\n")?;
+    run_git(&["branch", "-M", "main"], Some(&seed))?;
+    run_git(
+        &["remote", "add", "origin", remote.to_string_lossy().as_ref()],
+        Some(&seed),
+    )?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+    Ok(remote)
+}
+
+fn run_git(args: &[&str], dir: Option<&Path>) -> Result<()> {
+    let output = git_command(dir)
+        .args(args)
+        .output()
+        .context("git 実行に失敗しました")?;
+    if !output.status.success() {
+        bail!(
+            "git が失敗しました: {}\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+fn git_output(args: &[&str], dir: &Path) -> Result<String> {
+    let output = git_command(Some(dir))
+        .args(args)
+        .output()
+        .context("git 実行に失敗しました")?;
+    if !output.status.success() {
+        bail!(
+            "git が失敗しました: {}\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
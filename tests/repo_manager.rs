@@ -5,7 +5,11 @@ use std::{
     process::Command,
 };
 
-use aicloner::{config::Config, repo::RepoManager};
+use aicloner::{
+    config::{Config, WorkspaceStrategy},
+    doctor::{self, Finding},
+    repo::{detect_default_branch, RemoveOptions, RepoManager},
+};
 use anyhow::{bail, Context, Result};
 use tempfile::TempDir;
 
@@ -33,6 +37,7 @@ fn init_clones_base_main_branch() -> Result<()> {
         repo_url: remote.to_string_lossy().to_string(),
         base_dir: "base".to_string(),
         workspaces_dir: "ws".to_string(),
+        ..Default::default()
     };
     let manager = RepoManager::new(config, config_path);
     manager.init_environment("main")?;
@@ -46,6 +51,39 @@ fn init_clones_base_main_branch() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn default_branch_follows_remote_head() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+    let repo_url = remote.to_string_lossy().to_string();
+    // HEAD の参照先が無くても main があればそれを使う
+    assert_eq!(detect_default_branch(&repo_url)?, "main");
+
+    create_remote_branch_with_commit(&remote, "develop", "develop")?;
+    run_git(
+        &["symbolic-ref", "HEAD", "refs/heads/develop"],
+        Some(&remote),
+    )?;
+    assert_eq!(detect_default_branch(&repo_url)?, "develop");
+
+    let config = Config {
+        repo_url,
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        default_branch: Some("develop".to_string()),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, tmp.path().join(".aicloner.toml"));
+    manager.init_environment("develop")?;
+    assert_eq!(current_branch(&manager.base_dir())?, "develop");
+    assert_eq!(manager.default_branch()?, "develop");
+
+    manager.create_task_clone("task-a", &manager.default_branch()?)?;
+    let content = fs::read_to_string(manager.workspaces_dir().join("task-a").join("README.md"))?;
+    assert_eq!(normalize_newlines(&content), "develop\n");
+    Ok(())
+}
+
 #[test]
 fn create_clone_and_list_returns_branch() -> Result<()> {
     let tmp = TempDir::new()?;
@@ -57,6 +95,7 @@ fn create_clone_and_list_returns_branch() -> Result<()> {
         repo_url: remote.to_string_lossy().to_string(),
         base_dir: "base".to_string(),
         workspaces_dir: "ws".to_string(),
+        ..Default::default()
     };
     let manager = RepoManager::new(config, config_path);
     manager.init_environment("main")?;
@@ -88,6 +127,7 @@ fn clone_failure_shows_stderr_and_cleans_directory() -> Result<()> {
         repo_url: remote.to_string_lossy().to_string(),
         base_dir: "base".to_string(),
         workspaces_dir: "ws".to_string(),
+        ..Default::default()
     };
     let manager = RepoManager::new(config, config_path);
     manager.init_environment("main")?;
@@ -111,6 +151,7 @@ fn create_new_branch_when_remote_missing() -> Result<()> {
         repo_url: remote.to_string_lossy().to_string(),
         base_dir: "base".to_string(),
         workspaces_dir: "ws".to_string(),
+        ..Default::default()
     };
     let manager = RepoManager::new(config, config_path);
     manager.init_environment("main")?;
@@ -126,6 +167,385 @@ fn create_new_branch_when_remote_missing() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn worktree_strategy_creates_lists_and_removes_tasks() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+    create_remote_branch_with_commit(&remote, "task-a", "feature")?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        workspace_strategy: WorkspaceStrategy::Worktree,
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+
+    manager.create_task_clone("task-a", "main")?;
+    manager.create_task_clone("task-new", "main")?;
+
+    let existing = manager.workspaces_dir().join("task-a");
+    assert!(existing.join(".git").is_file());
+    assert_eq!(current_branch(&existing)?, "task-a");
+    let content = fs::read_to_string(existing.join("README.md"))?;
+    assert_eq!(normalize_newlines(&content), "feature\n");
+
+    let created = manager.workspaces_dir().join("task-new");
+    assert_eq!(current_branch(&created)?, "task-new");
+    let content = fs::read_to_string(created.join("README.md"))?;
+    assert_eq!(normalize_newlines(&content), "hello\n");
+
+    let tasks = manager.list_tasks()?;
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0].name, "task-a");
+    assert_eq!(tasks[0].branch.as_deref(), Some("task-a"));
+    assert_eq!(tasks[0].strategy, WorkspaceStrategy::Worktree);
+    assert_eq!(tasks[1].branch.as_deref(), Some("task-new"));
+
+    manager.remove_task_clone("task-new", &force_remove())?;
+    assert!(!created.exists());
+    let worktrees = capture_git(
+        &["worktree", "list", "--porcelain"],
+        Some(&manager.base_dir()),
+    )?;
+    assert!(!worktrees.contains("task-new"));
+    assert_eq!(manager.list_tasks()?.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn clone_shares_objects_with_base_until_repacked() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    let alternates = workspace.join(".git/objects/info/alternates");
+    assert!(alternates.exists());
+    assert_eq!(local_object_count(&workspace)?, 0);
+
+    assert!(manager.repack_task("task-a")?);
+    assert!(!alternates.exists());
+    assert!(local_object_count(&workspace)? > 0);
+    assert!(!manager.repack_task("task-a")?);
+
+    // 参照先のミラーと base を消してもワークスペースの履歴が読めること
+    fs::remove_dir_all(manager.mirror_dir())?;
+    fs::remove_dir_all(manager.base_dir())?;
+    run_git(&["fsck", "--connectivity-only"], Some(&workspace))?;
+    Ok(())
+}
+
+#[test]
+fn dissociate_copies_objects_into_clone() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        dissociate: true,
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    assert!(!workspace.join(".git/objects/info/alternates").exists());
+    assert!(local_object_count(&workspace)? > 0);
+    Ok(())
+}
+
+#[test]
+fn init_creates_mirror_and_keeps_origin_upstream() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+    let remote_url = remote.to_string_lossy().to_string();
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote_url.clone(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+
+    let mirror = manager.mirror_dir();
+    let is_bare = capture_git(&["rev-parse", "--is-bare-repository"], Some(&mirror))?;
+    assert_eq!(is_bare, "true");
+
+    manager.create_task_clone("task-a", "main")?;
+    let workspace = manager.workspaces_dir().join("task-a");
+    for repo in [manager.base_dir(), workspace] {
+        let origin = capture_git(&["remote", "get-url", "origin"], Some(&repo))?;
+        assert_eq!(origin, remote_url);
+    }
+    Ok(())
+}
+
+#[test]
+fn offline_task_creation_uses_mirror() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+    create_remote_branch_with_commit(&remote, "task-a", "feature")?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let mut manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+
+    // リモートに到達できない状態を再現する
+    fs::rename(&remote, tmp.path().join("unreachable.git"))?;
+    manager.offline = true;
+
+    manager.create_task_clone("task-a", "main")?;
+    manager.create_task_clone("task-new", "main")?;
+    let workspace = manager.workspaces_dir().join("task-a");
+    assert_eq!(current_branch(&workspace)?, "task-a");
+    let content = fs::read_to_string(workspace.join("README.md"))?;
+    assert_eq!(normalize_newlines(&content), "feature\n");
+    assert_eq!(
+        current_branch(&manager.workspaces_dir().join("task-new"))?,
+        "task-new"
+    );
+
+    let err = manager.fetch_mirror().unwrap_err().to_string();
+    assert!(err.contains("オフライン"));
+    Ok(())
+}
+
+#[test]
+fn offline_without_mirror_fails_fast() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let mut manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    fs::remove_dir_all(manager.mirror_dir())?;
+    manager.offline = true;
+
+    let err = manager.create_task_clone("task-a", "main").unwrap_err();
+    assert!(err.to_string().contains("aicloner fetch"));
+    assert!(!manager.workspaces_dir().join("task-a").exists());
+    Ok(())
+}
+
+#[test]
+fn fetch_mirror_picks_up_new_remote_branches() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        auto_fetch: false,
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    create_remote_branch_with_commit(&remote, "task-a", "feature")?;
+
+    manager.fetch_mirror()?;
+    manager.create_task_clone("task-a", "main")?;
+    let content = fs::read_to_string(manager.workspaces_dir().join("task-a/README.md"))?;
+    assert_eq!(normalize_newlines(&content), "feature\n");
+    Ok(())
+}
+
+#[test]
+fn task_metadata_is_recorded_and_removed() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+    manager.update_task_record("task-a", |record| record.issue = Some(12))?;
+
+    assert!(tmp.path().join(".aicloner/state.toml").exists());
+    let tasks = manager.list_tasks()?;
+    let record = tasks[0].record.as_ref().expect("task-a should be recorded");
+    assert_eq!(record.branch, "task-a");
+    assert_eq!(record.base_branch, "main");
+    assert_eq!(record.strategy, WorkspaceStrategy::Clone);
+    assert_eq!(record.issue, Some(12));
+    assert!(record.age().is_some());
+
+    let json = serde_json::to_value(&tasks[0])?;
+    assert_eq!(json["name"], "task-a");
+    assert_eq!(json["strategy"], "clone");
+    assert_eq!(json["record"]["issue"], 12);
+
+    manager.remove_task_clone("task-a", &force_remove())?;
+    assert!(manager.load_state()?.tasks.is_empty());
+    Ok(())
+}
+
+#[test]
+fn doctor_reconciles_state_with_directories() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+    manager.create_task_clone("task-b", "main")?;
+
+    // task-a は記録を失い、task-b はディレクトリを失った状態にする
+    let mut state = manager.load_state()?;
+    state.tasks.remove("task-a");
+    manager.save_state(&state)?;
+    fs::remove_dir_all(manager.workspaces_dir().join("task-b"))?;
+
+    let findings = doctor::diagnose(&manager)?;
+    assert_eq!(findings.len(), 2);
+    assert!(findings
+        .iter()
+        .any(|f| matches!(f, Finding::Orphan { task_name, .. } if task_name == "task-a")));
+    assert!(findings
+        .iter()
+        .any(|f| matches!(f, Finding::Missing { task_name, .. } if task_name == "task-b")));
+
+    doctor::fix(&manager, &findings)?;
+    assert!(doctor::diagnose(&manager)?.is_empty());
+    let state = manager.load_state()?;
+    assert_eq!(state.tasks.len(), 1);
+    assert_eq!(state.tasks["task-a"].branch, "task-a");
+    Ok(())
+}
+
+#[test]
+fn remove_refuses_unsaved_work_and_archives_it() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config_path = tmp.path().join(".aicloner.toml");
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        base_dir: "base".to_string(),
+        workspaces_dir: "ws".to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, config_path);
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+    manager.create_task_clone("task-b", "main")?;
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    run_git(&["config", "user.name", "tester"], Some(&workspace))?;
+    run_git(
+        &["config", "user.email", "tester@example.com"],
+        Some(&workspace),
+    )?;
+    fs::write(workspace.join("feature.txt"), "feature\n")?;
+    run_git(&["add", "feature.txt"], Some(&workspace))?;
+    run_git(&["commit", "-m", "Add feature"], Some(&workspace))?;
+    fs::write(workspace.join("README.md"), "changed\n")?;
+    fs::create_dir_all(workspace.join("notes"))?;
+    fs::write(workspace.join("notes/todo.txt"), "todo\n")?;
+
+    let yes_only = RemoveOptions {
+        yes: true,
+        ..Default::default()
+    };
+    let err = manager
+        .remove_task_clone("task-a", &yes_only)
+        .expect_err("unpushed work must block removal");
+    let message = format!("{:#}", err);
+    assert!(message.contains("Add feature"), "{}", message);
+    assert!(message.contains("README.md"), "{}", message);
+    assert!(workspace.exists());
+
+    // 作業が無いタスクは --force 無しで削除できる
+    manager.remove_task_clone("task-b", &yes_only)?;
+    assert!(!manager.workspaces_dir().join("task-b").exists());
+
+    let archive = RemoveOptions {
+        archive: true,
+        yes: true,
+        ..Default::default()
+    };
+    manager.remove_task_clone("task-a", &archive)?;
+    assert!(!workspace.exists());
+
+    let trash: Vec<PathBuf> = fs::read_dir(manager.trash_dir())?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    assert_eq!(trash.len(), 1);
+    let archive_dir = &trash[0];
+    let patch = fs::read_to_string(archive_dir.join("uncommitted.patch"))?;
+    assert!(patch.contains("+changed"));
+    assert_eq!(
+        fs::read_to_string(archive_dir.join("untracked/notes/todo.txt"))?,
+        "todo\n"
+    );
+    let bundle = archive_dir.join("unpushed.bundle");
+    run_git(
+        &["bundle", "verify", bundle.to_string_lossy().as_ref()],
+        Some(&manager.base_dir()),
+    )?;
+    let heads = capture_git(
+        &["bundle", "list-heads", bundle.to_string_lossy().as_ref()],
+        None,
+    )?;
+    assert!(heads.contains("refs/heads/task-a"));
+    Ok(())
+}
+
+fn force_remove() -> RemoveOptions {
+    RemoveOptions {
+        force: true,
+        yes: true,
+        ..Default::default()
+    }
+}
+
 fn init_remote_repo(tmp: &TempDir) -> Result<PathBuf> {
     let remote = tmp.path().join("remote.git");
     run_git(&["init", "--bare", remote.to_string_lossy().as_ref()], None)?;
@@ -171,6 +591,21 @@ fn create_remote_branch_with_commit(remote: &Path, branch: &str, content: &str)
     Ok(())
 }
 
+/// ワークスペース自身が保持するオブジェクト数 (loose + pack)
+fn local_object_count(repo: &Path) -> Result<u64> {
+    let output = capture_git(&["count-objects", "-v"], Some(repo))?;
+    let mut total = 0;
+    for line in output.lines() {
+        if let Some(value) = line
+            .strip_prefix("count: ")
+            .or_else(|| line.strip_prefix("in-pack: "))
+        {
+            total += value.trim().parse::<u64>()?;
+        }
+    }
+    Ok(total)
+}
+
 fn current_branch(repo: &Path) -> Result<String> {
     capture_git(&["rev-parse", "--abbrev-ref", "HEAD"], Some(repo))
 }
@@ -0,0 +1,54 @@
+#![cfg(unix)]
+
+mod common;
+
+use tempfile::TempDir;
+
+use anyhow::Result;
+
+use common::StartEnv;
+
+#[test]
+fn repos_registry_aggregates_with_all_repos() -> Result<()> {
+    let env = StartEnv::new()?;
+    assert!(env.run(&["start", "1"], "")?.status.success());
+    let outside = TempDir::new()?;
+
+    let output = env.command_in(outside.path()).args(["list", "--all-repos"]).output()?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("登録されているリポジトリがありません"));
+
+    let output = env
+        .command_in(outside.path())
+        .args(["repos", "add", "--name", "app"])
+        .arg(env.tmp.path())
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = env.command_in(outside.path()).args(["repos", "list"]).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("app"), "{}", stdout);
+
+    // 設定ファイルの外からでも、登録済みのリポジトリを REPO 列付きで一覧できる
+    let output = env.command_in(outside.path()).args(["list", "--all-repos"]).output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("REPO"), "{}", stdout);
+    assert!(stdout.contains("app") && stdout.contains("aicloner-issue1"), "{}", stdout);
+
+    let output = env
+        .command_in(outside.path())
+        .args(["issues", "--all-repos", "--format", "json"])
+        .output()?;
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json["issues"][0]["repo"], "app");
+    assert_eq!(json["issues"][0]["workspaces"][0], "aicloner-issue1");
+
+    let output = env.command_in(outside.path()).args(["repos", "forget", "app"]).output()?;
+    assert!(output.status.success());
+    let output = env.command_in(outside.path()).args(["list", "--all-repos"]).output()?;
+    assert!(!output.status.success());
+    Ok(())
+}
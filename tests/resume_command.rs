@@ -0,0 +1,41 @@
+#![cfg(unix)]
+
+mod common;
+
+use std::fs;
+
+use anyhow::Result;
+
+use common::StartEnv;
+
+#[test]
+fn resume_reopens_the_issue_workspace() -> Result<()> {
+    let env = StartEnv::new()?;
+    assert!(env.run(&["start", "1"], "")?.status.success());
+    let workspace = env.manager()?.workspaces_dir().join("aicloner-issue1");
+    fs::write(workspace.join("notes.txt"), "wip\n")?;
+
+    let output = env.run(&["resume", "#1"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let launched = fs::read_to_string(&env.stub_output)?;
+    assert!(launched.ends_with("--resume\n"), "{}", launched);
+
+    // --prompt なら現在の状態をまとめたプロンプトを渡す
+    let output = env.run(&["resume", "aicloner-issue1", "--prompt"], "")?;
+    assert!(output.status.success());
+    let launched = fs::read_to_string(&env.stub_output)?;
+    assert!(!launched.contains("--resume"), "{}", launched);
+    assert!(launched.contains("`aicloner-issue1` で進めていた作業を再開"), "{}", launched);
+    assert!(launched.contains("?? notes.txt"), "{}", launched);
+    assert!(launched.contains("Fix login"), "{}", launched);
+
+    let output = env.run(&["resume", "2"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("Issue #2 のワークスペースがありません"));
+    Ok(())
+}
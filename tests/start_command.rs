@@ -1,3 +1,5 @@
+mod common;
+
 use std::fs;
 use std::path::PathBuf;
 use tempfile::TempDir;
@@ -7,43 +9,9 @@ use anyhow::Result;
 use aicloner::config::Config;
 use aicloner::repo::RepoManager;
 
-fn init_remote_repo(tmp: &TempDir) -> Result<PathBuf> {
-    let remote_path = tmp.path().join("remote.git");
-    fs::create_dir(&remote_path)?;
-
-    let init_args = vec!["init".to_string(), "--bare".to_string()];
-    std::process::Command::new("git")
-        .args(&init_args)
-        .current_dir(&remote_path)
-        .output()?;
-
-    let dummy_path = tmp.path().join("dummy");
-    fs::create_dir(&dummy_path)?;
-    std::process::Command::new("git")
-        .args(["init"])
-        .current_dir(&dummy_path)
-        .output()?;
-
-    fs::write(dummy_path.join("README.md"), "# Test")?;
-    std::process::Command::new("git")
-        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "add", "."])
-        .current_dir(&dummy_path)
-        .output()?;
-    std::process::Command::new("git")
-        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "commit", "-m", "init"])
-        .current_dir(&dummy_path)
-        .output()?;
-    std::process::Command::new("git")
-        .args(["remote", "add", "origin", remote_path.to_str().unwrap()])
-        .current_dir(&dummy_path)
-        .output()?;
-    std::process::Command::new("git")
-        .args(["push", "-u", "origin", "main"])
-        .current_dir(&dummy_path)
-        .output()?;
-
-    Ok(remote_path)
-}
+use common::init_remote_repo;
+#[cfg(unix)]
+use common::StartEnv;
 
 #[test]
 fn task_exists_returns_true_for_existing_workspace() -> Result<()> {
@@ -55,6 +23,7 @@ fn task_exists_returns_true_for_existing_workspace() -> Result<()> {
         repo_url: remote.to_string_lossy().to_string(),
         base_dir: "base".to_string(),
         workspaces_dir: "ws".to_string(),
+        ..Default::default()
     };
     config.save(&config_path)?;
 
@@ -81,6 +50,7 @@ fn task_exists_returns_false_when_workspace_not_created() -> Result<()> {
         repo_url: remote.to_string_lossy().to_string(),
         base_dir: "base".to_string(),
         workspaces_dir: "ws".to_string(),
+        ..Default::default()
     };
     config.save(&config_path)?;
 
@@ -92,3 +62,273 @@ fn task_exists_returns_false_when_workspace_not_created() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(unix)]
+#[test]
+fn start_creates_workspace_and_launches_tool_with_issue_prompt() -> Result<()> {
+    let env = StartEnv::new()?;
+    let output = env.run(&["start", "1"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let manager = env.manager()?;
+    assert!(manager.task_exists("aicloner-issue1"));
+    let record = manager.load_state()?.tasks["aicloner-issue1"].clone();
+    assert_eq!(record.issue, Some(1));
+    assert_eq!(record.tool.as_deref(), Some("stub"));
+    assert_eq!(record.base_branch, "main");
+
+    // スタブはワークスペースで起動され、Issue の内容を含むプロンプトを受け取る
+    let launched = fs::read_to_string(&env.stub_output)?;
+    let mut lines = launched.lines();
+    let cwd = PathBuf::from(lines.next().unwrap());
+    assert_eq!(
+        cwd.canonicalize()?,
+        manager.workspaces_dir().join("aicloner-issue1").canonicalize()?
+    );
+    assert!(launched.contains("Fix login"));
+    assert!(launched.contains("Safari で押せない"));
+    assert!(launched.contains("bug"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn start_fails_for_unknown_issue_without_creating_workspace() -> Result<()> {
+    let env = StartEnv::new()?;
+    let output = env.run(&["start", "99"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Issue #99 が見つかりません"));
+    assert!(!env.manager()?.task_exists("aicloner-issue99"));
+    assert!(!env.stub_output.exists());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn start_resolves_existing_branch_from_flags() -> Result<()> {
+    let env = StartEnv::new()?;
+    assert!(env.run(&["start", "1"], "")?.status.success());
+    fs::remove_file(&env.stub_output)?;
+
+    // 標準入力が端末でなければ入力を待たずに止まる
+    let output = env.run(&["start", "1"], "1\n")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--on-conflict"));
+    assert!(!env.stub_output.exists());
+
+    let output = env.run(&["start", "1", "--on-conflict", "abort"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("中止しました"));
+    assert!(!env.stub_output.exists());
+
+    // ブランチ名を指定するとそのワークスペースで起動する
+    let output = env.run(&["start", "1", "--branch", "aicloner-issue1-retry"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let manager = env.manager()?;
+    assert!(manager.task_exists("aicloner-issue1-retry"));
+    assert!(fs::read_to_string(&env.stub_output)?.contains("aicloner-issue1-retry"));
+
+    // 既存のワークスペースに切り替えるとそこでセッションを再開する
+    let output = env.run(&["start", "1", "--on-conflict", "reuse"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let workspace = manager.workspaces_dir().join("aicloner-issue1").canonicalize()?;
+    let launched = fs::read_to_string(&env.stub_output)?;
+    assert!(
+        launched.starts_with(&format!("{}\n--resume\n", workspace.display())),
+        "{}",
+        launched
+    );
+
+    // suffix はワークスペースとリモートのどちらにも無い連番を選ぶ
+    let output = env.run(&["start", "1", "--on-conflict", "suffix"], "")?;
+    assert!(output.status.success());
+    assert!(manager.task_exists("aicloner-issue1-2"));
+
+    std::process::Command::new("git")
+        .args(["branch", "aicloner-issue1-3", "main"])
+        .current_dir(env.tmp.path().join("remote.git"))
+        .output()?;
+    assert!(env.run(&["fetch"], "")?.status.success());
+    let output = env.run(&["start", "1", "-y", "--on-conflict", "suffix"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(!manager.task_exists("aicloner-issue1-3"));
+    assert!(manager.task_exists("aicloner-issue1-4"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn start_names_branch_from_template() -> Result<()> {
+    let env = StartEnv::new()?;
+    let config = fs::read_to_string(&env.config_path)?;
+    fs::write(
+        &env.config_path,
+        format!("branch_template = \"feat/{{issue}}-{{slug}}\"\n{}", config),
+    )?;
+
+    let output = env.run(&["start", "1"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // ブランチの `/` はタスク名では `-` になる
+    let manager = env.manager()?;
+    assert!(manager.task_exists("feat-1-fix-login"));
+    let record = manager.load_state()?.tasks["feat-1-fix-login"].clone();
+    assert_eq!(record.branch, "feat/1-fix-login");
+    let head = std::process::Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .current_dir(manager.workspaces_dir().join("feat-1-fix-login"))
+        .output()?;
+    assert_eq!(String::from_utf8_lossy(&head.stdout).trim(), "feat/1-fix-login");
+
+    // 衝突の検出と Issue からのタスクの検索も同じテンプレートを使う
+    let output = env.run(&["start", "1", "--on-conflict", "suffix"], "")?;
+    assert!(output.status.success());
+    assert!(manager.task_exists("feat-1-fix-login-2"));
+    let output = env.run(&["resume", "1"], "")?;
+    assert!(String::from_utf8_lossy(&output.stderr).contains("複数あります"));
+
+    fs::write(
+        &env.config_path,
+        format!("branch_template = \"feat/{{title}}\"\n{}", config),
+    )?;
+    let output = env.run(&["list"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("branch_template"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn start_from_cuts_workspace_from_another_branch() -> Result<()> {
+    let env = StartEnv::new()?;
+    std::process::Command::new("git")
+        .args(["branch", "release/1.0", "main"])
+        .current_dir(env.tmp.path().join("remote.git"))
+        .output()?;
+    assert!(env.run(&["fetch"], "")?.status.success());
+
+    let output = env.run(&["start", "1", "--from", "release/1.0"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let manager = env.manager()?;
+    let record = manager.load_state()?.tasks["aicloner-issue1"].clone();
+    assert_eq!(record.base_branch, "release/1.0");
+
+    let output = env.run(&["start", "2", "--from", "release/9.9"], "")?;
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("リモートにありません"));
+    assert!(!manager.task_exists("aicloner-issue2"));
+    Ok(())
+}
+
+#[cfg(unix)]
+fn session_log(env: &StartEnv, task: &str) -> Result<String> {
+    let dir = env.manager()?.logs_dir().join(task);
+    let mut logs: Vec<PathBuf> = fs::read_dir(&dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    logs.sort();
+    let last = logs.last().expect("ログファイルがありません");
+    Ok(fs::read_to_string(last)?)
+}
+
+#[cfg(unix)]
+#[test]
+fn start_headless_runs_each_issue_with_a_log() -> Result<()> {
+    let env = StartEnv::new()?;
+    let output = env.run(&["start", "1", "2", "--headless", "--max-parallel", "1"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    for (task, title) in [("aicloner-issue1", "Fix login"), ("aicloner-issue2", "Update docs")] {
+        let log = session_log(&env, task)?;
+        assert!(log.contains("--headless"), "{}", log);
+        assert!(log.contains(title), "{}", log);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("完了"), "{}", stdout);
+
+    // 複数の Issue は --headless が必要
+    let output = env.run(&["start", "1", "2"], "")?;
+    assert!(!output.status.success());
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn start_headless_reports_failed_sessions() -> Result<()> {
+    let env = StartEnv::new()?;
+    let output = env.run(&["start", "99", "--label", "bug", "--headless"], "")?;
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 件のセッションが失敗しました"), "{}", stderr);
+
+    // 失敗した Issue があっても他のセッションは実行する (閉じた #3 は含めない)
+    assert!(session_log(&env, "aicloner-issue1")?.contains("Fix login"));
+    assert!(!env.manager()?.task_exists("aicloner-issue3"));
+    assert!(!env.manager()?.task_exists("aicloner-issue99"));
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn start_record_saves_transcript_for_logs() -> Result<()> {
+    let env = StartEnv::new()?;
+    let output = env.run(&["start", "1", "--record"], "")?;
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    // 擬似端末の出力は端末にも流す
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Fix login"));
+
+    let output = env.run(&["logs", "aicloner-issue1"], "")?;
+    assert!(output.status.success());
+    let log = String::from_utf8_lossy(&output.stdout);
+    assert!(log.contains("# task: aicloner-issue1"), "{}", log);
+    assert!(
+        log.lines()
+            .any(|line| line.starts_with('[') && line.contains("Fix login")),
+        "{}",
+        log
+    );
+    assert!(log.contains("# exit: 0"), "{}", log);
+
+    // 終了済みのセッションなら --follow もすぐに戻る
+    let output = env.run(&["logs", "aicloner-issue1", "--follow"], "")?;
+    assert!(output.status.success());
+
+    let output = env.run(&["logs", "aicloner-issue1", "--list"], "")?;
+    let list = String::from_utf8_lossy(&output.stdout);
+    assert!(list.starts_with("LOG"), "{}", list);
+    assert_eq!(list.lines().count(), 2, "{}", list);
+
+    let output = env.run(&["logs", "aicloner-issue2"], "")?;
+    assert!(!output.status.success());
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use aicloner::{
+    config::Config,
+    repo::RepoManager,
+    status::{collect_statuses, Divergence, PrStatus, StatusOptions},
+};
+use anyhow::{bail, Context, Result};
+use tempfile::TempDir;
+
+fn git_command(dir: Option<&Path>) -> Command {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    cmd.env("GIT_CONFIG_GLOBAL", null_path)
+        .env("GIT_CONFIG_SYSTEM", null_path)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_ATTR_NOSYSTEM", "1");
+    cmd
+}
+
+#[test]
+fn status_reports_changes_divergence_and_last_commit() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let remote = init_remote_repo(&tmp)?;
+
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, tmp.path().join(".aicloner.toml"));
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+    manager.create_task_clone("task-b", "main")?;
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    run_git(&["config", "user.name", "tester"], Some(&workspace))?;
+    run_git(
+        &["config", "user.email", "tester@example.com"],
+        Some(&workspace),
+    )?;
+    fs::write(workspace.join("feature.txt"), "feature\n")?;
+    run_git(&["add", "feature.txt"], Some(&workspace))?;
+    run_git(&["commit", "-m", "Add feature"], Some(&workspace))?;
+    fs::write(workspace.join("README.md"), "changed\n")?;
+    fs::write(workspace.join("notes.txt"), "notes\n")?;
+
+    let tasks = manager.list_tasks()?;
+    let options = StatusOptions {
+        check_pull_requests: false,
+    };
+    let statuses = collect_statuses(&manager, &tasks, &options);
+    assert_eq!(statuses.len(), 2);
+
+    let task_a = &statuses[0];
+    assert_eq!(task_a.name, "task-a");
+    assert_eq!(task_a.modified, 1);
+    assert_eq!(task_a.untracked, 1);
+    assert_eq!(task_a.upstream, None);
+    assert_eq!(
+        task_a.base,
+        Some(Divergence {
+            ahead: 1,
+            behind: 0
+        })
+    );
+    assert_eq!(
+        task_a.last_commit.as_ref().map(|c| c.subject.as_str()),
+        Some("Add feature")
+    );
+    assert_eq!(task_a.pull_request, PrStatus::Unknown);
+
+    let json = serde_json::to_value(task_a)?;
+    assert_eq!(json["base"], serde_json::json!({"ahead": 1, "behind": 0}));
+    assert_eq!(
+        json["pull_request"],
+        serde_json::json!({"status": "unknown"})
+    );
+    assert_eq!(json["last_commit"]["subject"], "Add feature");
+
+    let task_b = &statuses[1];
+    assert_eq!(task_b.name, "task-b");
+    assert_eq!((task_b.modified, task_b.untracked), (0, 0));
+    assert_eq!(
+        task_b.last_commit.as_ref().map(|c| c.subject.as_str()),
+        Some("init")
+    );
+    Ok(())
+}
+
+fn init_remote_repo(tmp: &TempDir) -> Result<PathBuf> {
+    let remote = tmp.path().join("remote.git");
+    run_git(&["init", "--bare", remote.to_string_lossy().as_ref()], None)?;
+
+    let seed = tmp.path().join("seed");
+    fs::create_dir_all(&seed)?;
+    run_git(&["init"], Some(&seed))?;
+    run_git(&["config", "user.name", "tester"], Some(&seed))?;
+    run_git(&["config", "user.email", "tester@example.com"], Some(&seed))?;
+    fs::write(seed.join("README.md"), "hello\n")?;
+    run_git(&["add", "README.md"], Some(&seed))?;
+    run_git(&["commit", "-m", "init"], Some(&seed))?;
+    run_git(&["branch", "-M", "main"], Some(&seed))?;
+    run_git(
+        &["remote", "add", "origin", remote.to_string_lossy().as_ref()],
+        Some(&seed),
+    )?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+    Ok(remote)
+}
+
+fn run_git(args: &[&str], dir: Option<&Path>) -> Result<()> {
+    let output = git_command(dir)
+        .args(args)
+        .output()
+        .context("git 実行に失敗しました")?;
+    if !output.status.success() {
+        bail!(
+            "git が失敗しました: {}\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use aicloner::{
+    config::{Config, SyncMethod, WorkspaceStrategy},
+    repo::RepoManager,
+    sync::{sync_tasks, SyncOptions, SyncOutcome},
+};
+use anyhow::{bail, Context, Result};
+use tempfile::TempDir;
+
+fn git_command(dir: Option<&Path>) -> Command {
+    let mut cmd = Command::new("git");
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+    let null_path = if cfg!(windows) { "NUL" } else { "/dev/null" };
+    cmd.env("GIT_CONFIG_GLOBAL", null_path)
+        .env("GIT_CONFIG_SYSTEM", null_path)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_ATTR_NOSYSTEM", "1");
+    cmd
+}
+
+/// リモート・base・タスク "task-a" (ローカルコミット 1 件) を用意する
+fn setup(tmp: &TempDir, strategy: WorkspaceStrategy) -> Result<(PathBuf, RepoManager)> {
+    let remote = tmp.path().join("remote.git");
+    run_git(&["init", "--bare", remote.to_string_lossy().as_ref()], None)?;
+    let seed = tmp.path().join("seed");
+    run_git(
+        &[
+            "clone",
+            remote.to_string_lossy().as_ref(),
+            seed.to_string_lossy().as_ref(),
+        ],
+        None,
+    )?;
+    configure_identity(&seed)?;
+    run_git(&["checkout", "-b", "main"], Some(&seed))?;
+    commit_file(&seed, "README.md", "hello\n", "init")?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+
+    let config = Config {
+        repo_url: remote.to_string_lossy().to_string(),
+        workspace_strategy: strategy,
+        ..Default::default()
+    };
+    let manager = RepoManager::new(config, tmp.path().join(".aicloner.toml"));
+    manager.init_environment("main")?;
+    manager.create_task_clone("task-a", "main")?;
+    let workspace = manager.workspaces_dir().join("task-a");
+    configure_identity(&workspace)?;
+    commit_file(&workspace, "task.txt", "task\n", "task work")?;
+    Ok((seed, manager))
+}
+
+fn options(method: SyncMethod, autostash: bool) -> SyncOptions {
+    SyncOptions { method, autostash }
+}
+
+#[test]
+fn sync_fast_forwards_base_and_rebases_task() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let (seed, manager) = setup(&tmp, WorkspaceStrategy::Clone)?;
+    commit_file(&seed, "upstream.txt", "upstream\n", "upstream change")?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+
+    let results = sync_tasks(
+        &manager,
+        &["task-a".to_string()],
+        &options(SyncMethod::Rebase, false),
+    )?;
+    assert_eq!(results[0].outcome, SyncOutcome::Updated);
+    assert!(manager.base_dir().join("upstream.txt").exists());
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    assert!(workspace.join("upstream.txt").exists());
+    assert_eq!(
+        capture_git(&["log", "-1", "--format=%s"], Some(&workspace))?,
+        "task work"
+    );
+    assert_eq!(
+        capture_git(
+            &["rev-list", "--merges", "--count", "HEAD"],
+            Some(&workspace)
+        )?,
+        "0"
+    );
+
+    let again = sync_tasks(
+        &manager,
+        &["task-a".to_string()],
+        &options(SyncMethod::Rebase, false),
+    )?;
+    assert_eq!(again[0].outcome, SyncOutcome::UpToDate);
+    Ok(())
+}
+
+#[test]
+fn sync_merges_into_worktree_task() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let (seed, manager) = setup(&tmp, WorkspaceStrategy::Worktree)?;
+    commit_file(&seed, "upstream.txt", "upstream\n", "upstream change")?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+
+    let results = sync_tasks(
+        &manager,
+        &["task-a".to_string()],
+        &options(SyncMethod::Merge, false),
+    )?;
+    assert_eq!(results[0].outcome, SyncOutcome::Updated);
+    let workspace = manager.workspaces_dir().join("task-a");
+    assert!(workspace.join("upstream.txt").exists());
+    assert_eq!(
+        capture_git(
+            &["rev-list", "--merges", "--count", "HEAD"],
+            Some(&workspace)
+        )?,
+        "1"
+    );
+    Ok(())
+}
+
+#[test]
+fn sync_skips_dirty_task_unless_autostash() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let (seed, manager) = setup(&tmp, WorkspaceStrategy::Clone)?;
+    commit_file(&seed, "upstream.txt", "upstream\n", "upstream change")?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    fs::write(workspace.join("task.txt"), "work in progress\n")?;
+    let head_before = capture_git(&["rev-parse", "HEAD"], Some(&workspace))?;
+
+    let results = sync_tasks(
+        &manager,
+        &["task-a".to_string()],
+        &options(SyncMethod::Rebase, false),
+    )?;
+    assert_eq!(results[0].outcome, SyncOutcome::DirtySkipped);
+    assert_eq!(
+        capture_git(&["rev-parse", "HEAD"], Some(&workspace))?,
+        head_before
+    );
+
+    let results = sync_tasks(
+        &manager,
+        &["task-a".to_string()],
+        &options(SyncMethod::Rebase, true),
+    )?;
+    assert_eq!(results[0].outcome, SyncOutcome::Updated);
+    assert!(workspace.join("upstream.txt").exists());
+    let content = fs::read_to_string(workspace.join("task.txt"))?;
+    assert_eq!(content.replace("\r\n", "\n"), "work in progress\n");
+    Ok(())
+}
+
+#[test]
+fn sync_reports_conflicts_and_restores_task() -> Result<()> {
+    let tmp = TempDir::new()?;
+    let (seed, manager) = setup(&tmp, WorkspaceStrategy::Clone)?;
+    commit_file(&seed, "task.txt", "upstream\n", "conflicting change")?;
+    run_git(&["push", "origin", "main"], Some(&seed))?;
+
+    let workspace = manager.workspaces_dir().join("task-a");
+    let head_before = capture_git(&["rev-parse", "HEAD"], Some(&workspace))?;
+    let results = sync_tasks(
+        &manager,
+        &["task-a".to_string()],
+        &options(SyncMethod::Rebase, false),
+    )?;
+    assert_eq!(results[0].outcome, SyncOutcome::Conflicts);
+    assert_eq!(
+        capture_git(&["rev-parse", "HEAD"], Some(&workspace))?,
+        head_before
+    );
+    assert_eq!(
+        capture_git(&["status", "--porcelain"], Some(&workspace))?,
+        ""
+    );
+    Ok(())
+}
+
+fn configure_identity(repo: &Path) -> Result<()> {
+    run_git(&["config", "user.name", "tester"], Some(repo))?;
+    run_git(&["config", "user.email", "tester@example.com"], Some(repo))
+}
+
+fn commit_file(repo: &Path, file: &str, content: &str, message: &str) -> Result<()> {
+    fs::write(repo.join(file), content)?;
+    run_git(&["add", file], Some(repo))?;
+    run_git(&["commit", "-m", message], Some(repo))
+}
+
+fn run_git(args: &[&str], dir: Option<&Path>) -> Result<()> {
+    let output = git_command(dir)
+        .args(args)
+        .output()
+        .context("git 実行に失敗しました")?;
+    if !output.status.success() {
+        bail!(
+            "git が失敗しました: {}\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+fn capture_git(args: &[&str], dir: Option<&Path>) -> Result<String> {
+    let output = git_command(dir)
+        .args(args)
+        .output()
+        .context("git 実行に失敗しました")?;
+    if !output.status.success() {
+        bail!(
+            "git が失敗しました: {}\nstderr: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}